@@ -1,42 +1,149 @@
-use std::time::SystemTime;
+use std::{net::SocketAddr, sync::Arc, time::SystemTime};
 
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Request, State,
+    },
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     Json,
 };
+use eyre::WrapErr;
 use serde::{Deserialize, Serialize};
-use tokio::{net::TcpListener, sync::mpsc::UnboundedSender};
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::UnboundedSender,
+    },
+};
 use tower_http::cors::{self, CorsLayer};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use nomad_types::{primitives::hex, SignalPayload};
+use nomad_pool::SignalPool;
+use nomad_types::{
+    primitives::{hex, B256},
+    SignalPayload,
+};
+use nomad_vm::Program;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_scalar::{Scalar, Servable};
 
+pub mod prometheus;
+mod status;
+#[cfg(test)]
+mod tests;
 pub mod types;
 
-use crate::types::{HealthResponse, RelayGetResponse, SignalRequest};
+use crate::{
+    status::StatusMap,
+    types::{
+        HealthResponse, PoolStatus, RelayGetResponse, SignalAccepted, SignalRequest, SignalStatus,
+        SignalUpdate,
+    },
+};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct ApiConfig {
+    /// Port to bind on all interfaces
     pub port: u16,
+    /// Routes served on the default port
+    pub routes: RouteSet,
+    /// Additional interfaces to bind, each with their own set of routes
+    pub binds: Vec<BindConfig>,
+    /// Optional bearer token required to submit signals
+    pub auth_token: Option<String>,
+    /// Serve metrics in the prometheus text format at `/metrics` with the admin routes
+    pub prometheus: bool,
+    /// Reader registered on the meter provider, collected on each `/metrics` request
+    #[serde(skip)]
+    pub prometheus_reader: Option<prometheus::PrometheusReader>,
+}
+
+impl std::fmt::Debug for ApiConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Hide the auth token
+        f.debug_struct("ApiConfig")
+            .field("port", &self.port)
+            .field("routes", &self.routes)
+            .field("binds", &self.binds)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("prometheus", &self.prometheus)
+            .finish()
+    }
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
-        Self { port: 8000 }
+        Self {
+            port: 8000,
+            routes: RouteSet::All,
+            binds: Vec::new(),
+            auth_token: None,
+            prometheus: false,
+            prometheus_reader: None,
+        }
+    }
+}
+
+/// Additional api server interface
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BindConfig {
+    /// Socket address to bind to, for example `127.0.0.1:8001`
+    pub address: SocketAddr,
+    /// Routes served on this interface
+    pub routes: RouteSet,
+}
+
+/// Set of routes served on an interface
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteSet {
+    /// Routes for signal submitters and load balancers
+    Public,
+    /// Operator routes which should not be exposed publicly
+    Admin,
+    /// Both public and admin routes
+    All,
+}
+
+impl RouteSet {
+    /// Build the router for the set of routes, requiring the auth token for signal submission if set
+    fn router(self, auth_token: Option<&str>, prometheus: bool) -> OpenApiRouter<AppState> {
+        let router = OpenApiRouter::with_openapi(ApiDoc::openapi());
+        match self {
+            RouteSet::Public => router.merge(public_routes(auth_token)),
+            RouteSet::Admin => router.merge(admin_routes(prometheus)),
+            RouteSet::All => router
+                .merge(public_routes(auth_token))
+                .merge(admin_routes(prometheus)),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub signal_tx: UnboundedSender<SignalPayload>,
+    /// Status updates for signals processed by the node
+    pub status_tx: broadcast::Sender<SignalUpdate>,
+    pub signal_pool: SignalPool,
+    /// Last known status of recent signals
+    statuses: StatusMap,
     pub start_time: SystemTime,
     pub is_bootstrap: bool,
     pub read_only: bool,
+    /// Reader to collect metrics from for `/metrics`
+    prometheus: Option<prometheus::PrometheusReader>,
 }
 
 #[utoipa::path(
@@ -57,12 +164,56 @@ async fn health(State(app_state): State<AppState>) -> Json<HealthResponse> {
     })
 }
 
+#[utoipa::path(
+    get, path = "/pool",
+    responses(
+        (status = OK, body = PoolStatus)
+    )
+)]
+async fn pool_status(State(app_state): State<AppState>) -> Json<PoolStatus> {
+    let pool = &app_state.signal_pool;
+    Json(PoolStatus {
+        size: pool.len(),
+        max_size: pool.max_size(),
+        total_inserted: pool.total_inserted(),
+        duplicates: pool.total_duplicates(),
+    })
+}
+
+#[utoipa::path(
+    get, path = "/metrics",
+    responses(
+        (status = OK, body = str, description = "Metrics in the prometheus text exposition format"),
+        (status = INTERNAL_SERVER_ERROR, body = str, description = "Failed to collect metrics")
+    )
+)]
+async fn metrics(State(app_state): State<AppState>) -> Response {
+    let Some(reader) = app_state.prometheus else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "No metrics reader registered",
+        )
+            .into_response();
+    };
+    match reader.render() {
+        Ok(text) => ([(CONTENT_TYPE, "text/plain; version=0.0.4")], text).into_response(),
+        Err(e) => {
+            warn!("Failed to collect metrics: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to collect metrics",
+            )
+                .into_response()
+        }
+    }
+}
+
 #[utoipa::path(
     post, path = "/signal",
     request_body = SignalRequest,
     responses(
-        (status = OK, body = str, description = "Signal acknowledged"),
-        (status = BAD_REQUEST, body = str, description = "Signal puzzle must have at least 500 bytes"),
+        (status = OK, body = SignalAccepted, description = "Signal acknowledged"),
+        (status = BAD_REQUEST, body = str, description = "Signal puzzle must have at least 500 bytes and pass validation"),
         (status = INTERNAL_SERVER_ERROR, body = str, description = "Failed to broadcast signal")
     )
 )]
@@ -70,9 +221,134 @@ async fn signal(
     State(app_state): State<AppState>,
     headers: HeaderMap,
     Json(req): Json<SignalRequest>,
-) -> (StatusCode, String) {
+) -> Result<Json<SignalAccepted>, (StatusCode, String)> {
+    let signal = accept_signal(&app_state, &headers, req).await?;
+    Ok(Json(SignalAccepted { id: signal.id() }))
+}
+
+#[utoipa::path(
+    get, path = "/signal/{id}",
+    params(
+        ("id" = String, Path, description = "Signal id returned when the signal was submitted")
+    ),
+    responses(
+        (status = OK, body = SignalStatus, description = "Last known status of the signal"),
+        (status = NOT_FOUND, body = str, description = "Signal is unknown or no longer tracked")
+    )
+)]
+async fn signal_status(
+    State(app_state): State<AppState>,
+    Path(id): Path<B256>,
+) -> Result<Json<SignalStatus>, (StatusCode, String)> {
+    app_state
+        .statuses
+        .get(&id)
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Unknown signal".to_string()))
+}
+
+#[utoipa::path(
+    get, path = "/signal/ws",
+    responses(
+        (status = SWITCHING_PROTOCOLS, body = SignalStatus, description = "Upgraded to a websocket. \
+            The first message sent must be a signal request, after which status events are streamed \
+            back until the signal is executed or fails")
+    )
+)]
+async fn signal_ws(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_signal(socket, app_state, headers))
+}
+
+/// Accept a signal from the socket and stream status events back for it
+async fn stream_signal(mut socket: WebSocket, app_state: AppState, headers: HeaderMap) {
+    let req = match socket.recv().await {
+        Some(Ok(Message::Text(text))) => serde_json::from_str::<SignalRequest>(&text)
+            .map_err(|e| format!("Invalid signal request: {e}")),
+        Some(Ok(_)) => Err("Expected a text message containing the signal request".into()),
+        _ => return,
+    };
+
+    // Subscribe before submitting, so no updates are missed
+    let mut updates = app_state.status_tx.subscribe();
+    let result = match req {
+        Ok(req) => accept_signal(&app_state, &headers, req)
+            .await
+            .map_err(|(_, reason)| reason),
+        Err(reason) => Err(reason),
+    };
+    let signal = match result {
+        Ok(signal) => signal,
+        Err(reason) => {
+            send_status(&mut socket, &SignalStatus::Failed { reason }).await;
+            return;
+        }
+    };
+    if !send_status(&mut socket, &SignalStatus::Received).await {
+        return;
+    }
+
+    loop {
+        match updates.recv().await {
+            Ok(update) if update.signal == signal => {
+                if !send_status(&mut socket, &update.status).await || update.status.is_final() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(n)) => warn!("Signal status stream lagged by {n} updates"),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Send a status event over the socket, returning false if the socket is closed
+async fn send_status(socket: &mut WebSocket, status: &SignalStatus) -> bool {
+    let text = serde_json::to_string(status).expect("status is always serializable");
+    socket.send(Message::Text(text.into())).await.is_ok()
+}
+
+/// Validate and broadcast a signal request, returning the submitted payload
+async fn accept_signal(
+    app_state: &AppState,
+    headers: &HeaderMap,
+    req: SignalRequest,
+) -> Result<SignalPayload, (StatusCode, String)> {
     // Validate signal
+    if let SignalRequest::Unencrypted(signal) = &req {
+        if let Err(e) = signal.validate() {
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid signal: {e}")));
+        }
+    }
     if let SignalRequest::Encrypted(signal) = &req {
+        // Cheap local checks first, so invalid payloads never reach the relay.
+        // simple check to make sure we have 12 byte nonce + some encrypted data in the signal
+        if signal.data.len() < 24 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Encrypted data is not big enough for the nonce and signal data".to_string(),
+            ));
+        }
+
+        // simple check to make sure the puzzle is at least 500 bytes
+        if signal.puzzle.len() < 500 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Signal puzzle must have at least 500 bytes".to_string(),
+            ));
+        }
+
+        // statically validate the puzzle program before accepting it
+        if let Err(e) = Program::from_bytes(&signal.puzzle).and_then(|p| p.validate()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid signal puzzle: {e}"),
+            ));
+        }
+
         // Ensure relay status is expected
         let res = reqwest::Client::new()
             .get(signal.relay.clone())
@@ -83,42 +359,26 @@ async fn signal(
             Ok(r) => match r.json::<RelayGetResponse>().await {
                 Ok(r) => {
                     if &r.status != "ok" || &r.service != "relay" {
-                        return (
+                        return Err((
                             StatusCode::BAD_REQUEST,
                             format!("Unexpected relay status, got: {r:?}"),
-                        );
+                        ));
                     }
                 }
                 Err(e) => {
-                    return (
+                    return Err((
                         StatusCode::BAD_REQUEST,
                         format!("Failed to read relay status: {e}"),
-                    )
+                    ))
                 }
             },
             Err(e) => {
-                return (
+                return Err((
                     StatusCode::BAD_REQUEST,
                     format!("Invalid relay status response: {e}"),
-                )
+                ))
             }
         };
-
-        // simple check to make sure we have 12 byte nonce + some encrypted data in the signal
-        if signal.data.len() < 24 {
-            return (
-                StatusCode::BAD_REQUEST,
-                "Encrypted data is not big enough for the nonce and signal data".to_string(),
-            );
-        }
-
-        // simple check to make sure the puzzle is at least 500 bytes
-        if signal.puzzle.len() < 500 {
-            return (
-                StatusCode::BAD_REQUEST,
-                "Signal puzzle must have at least 500 bytes".to_string(),
-            );
-        }
     }
 
     let signal = (|| {
@@ -136,53 +396,129 @@ async fn signal(
         req.untraced()
     })();
 
-    if app_state.signal_tx.send(signal).is_err() {
+    app_state.signal_tx.send(signal.clone()).map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to broadcast signal".to_string(),
         )
-    } else {
-        (StatusCode::OK, "Signal acknowledged".into())
-    }
+    })?;
+    app_state
+        .statuses
+        .insert(signal.id(), SignalStatus::Received);
+    Ok(signal)
 }
 
 #[derive(OpenApi)]
 #[openapi()]
 struct ApiDoc;
 
+/// Routes for signal submitters and load balancers
+fn public_routes(auth_token: Option<&str>) -> OpenApiRouter<AppState> {
+    let mut signal_routes = OpenApiRouter::new()
+        .routes(routes!(signal))
+        .routes(routes!(signal_ws));
+    if let Some(token) = auth_token {
+        signal_routes = signal_routes.route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_auth,
+        ));
+    }
+    OpenApiRouter::new()
+        .routes(routes!(health))
+        .routes(routes!(signal_status))
+        .merge(signal_routes)
+}
+
+/// Reject requests without a matching `Authorization: Bearer` header
+async fn require_auth(State(token): State<Arc<str>>, req: Request, next: Next) -> Response {
+    let authorized = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|v| v == &*token);
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid auth token").into_response();
+    }
+    next.run(req).await
+}
+
+/// Operator routes which should not be exposed publicly
+fn admin_routes(prometheus: bool) -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new().routes(routes!(pool_status));
+    if prometheus {
+        router.routes(routes!(metrics))
+    } else {
+        router
+    }
+}
+
+/// Bind and spawn the api server on each configured interface, returning the bound addresses
 pub async fn spawn_api_server(
     config: ApiConfig,
     is_bootstrap: bool,
     read_only: bool,
     signal_tx: UnboundedSender<SignalPayload>,
-) -> eyre::Result<()> {
+    status_tx: broadcast::Sender<SignalUpdate>,
+    signal_pool: SignalPool,
+) -> eyre::Result<Vec<SocketAddr>> {
     debug!(?config);
+    if config.prometheus && config.prometheus_reader.is_none() {
+        warn!("Prometheus metrics are enabled without a registered reader");
+    }
 
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(health, signal))
-        .split_for_parts();
-
-    let app = router
-        .merge(Scalar::with_url("/scalar", api))
-        .layer(
-            CorsLayer::new()
-                .allow_origin(cors::Any)
-                .allow_headers(cors::Any)
-                .allow_methods([
-                    axum::http::Method::GET,
-                    axum::http::Method::POST,
-                    axum::http::Method::OPTIONS,
-                ]),
-        )
-        .with_state(AppState {
-            is_bootstrap,
-            read_only,
-            signal_tx,
-            start_time: SystemTime::now(),
-        });
-
-    let listener = TcpListener::bind(("0.0.0.0", config.port)).await?;
-    info!("RPC server running on {:?}", listener.local_addr().unwrap());
-    tokio::spawn(async move { axum::serve(listener, app).await });
-    Ok(())
+    // Track signal statuses reported by the node
+    let statuses = StatusMap::default();
+    tokio::spawn(statuses.clone().track(status_tx.subscribe()));
+
+    let state = AppState {
+        is_bootstrap,
+        read_only,
+        signal_tx,
+        status_tx,
+        signal_pool,
+        statuses,
+        start_time: SystemTime::now(),
+        prometheus: config.prometheus_reader,
+    };
+
+    let binds = std::iter::once(BindConfig {
+        address: ([0, 0, 0, 0], config.port).into(),
+        routes: config.routes,
+    })
+    .chain(config.binds);
+
+    let mut addresses = Vec::new();
+    for bind in binds {
+        let (router, api) = bind
+            .routes
+            .router(config.auth_token.as_deref(), config.prometheus)
+            .split_for_parts();
+        let app = router
+            .merge(Scalar::with_url("/scalar", api))
+            .layer(
+                CorsLayer::new()
+                    .allow_origin(cors::Any)
+                    .allow_headers(cors::Any)
+                    .allow_methods([
+                        axum::http::Method::GET,
+                        axum::http::Method::POST,
+                        axum::http::Method::OPTIONS,
+                    ]),
+            )
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind(bind.address)
+            .await
+            .wrap_err_with(|| format!("Failed to bind API server to {}", bind.address))?;
+        let address = listener.local_addr()?;
+        info!(
+            "API server running on {address:?} ({:?} routes)",
+            bind.routes
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addresses.push(address);
+    }
+
+    Ok(addresses)
 }
@@ -0,0 +1,185 @@
+//! Prometheus text exposition of OpenTelemetry metrics.
+//!
+//! [`PrometheusReader`] is registered on the meter provider as a pull based
+//! reader, collecting and rendering the text format served at `/metrics` on request.
+
+use std::{
+    fmt::{Display, Write},
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{
+    error::{OTelSdkError, OTelSdkResult},
+    metrics::{
+        data::{AggregatedMetrics, MetricData, ResourceMetrics},
+        reader::MetricReader,
+        InstrumentKind, ManualReader, Pipeline, Temporality,
+    },
+};
+
+/// Metric reader collecting metrics in prometheus text format whenever they are rendered
+#[derive(Clone, Default, Debug)]
+pub struct PrometheusReader {
+    reader: Arc<ManualReader>,
+}
+
+impl PrometheusReader {
+    /// Collect the current metrics in prometheus text format
+    pub fn render(&self) -> Result<String, OTelSdkError> {
+        let mut metrics = ResourceMetrics::default();
+        self.reader.collect(&mut metrics)?;
+        Ok(encode(&metrics))
+    }
+}
+
+impl MetricReader for PrometheusReader {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.reader.register_pipeline(pipeline)
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> OTelSdkResult {
+        self.reader.collect(rm)
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.reader.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.reader.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.reader.temporality(kind)
+    }
+}
+
+/// Encode collected metrics in the prometheus text exposition format
+fn encode(metrics: &ResourceMetrics) -> String {
+    let mut out = String::new();
+    for metric in metrics.scope_metrics().flat_map(|scope| scope.metrics()) {
+        let name = sanitize(metric.name());
+        match metric.data() {
+            AggregatedMetrics::F64(data) => {
+                encode_data(&mut out, &name, metric.description(), data)
+            }
+            AggregatedMetrics::U64(data) => {
+                encode_data(&mut out, &name, metric.description(), data)
+            }
+            AggregatedMetrics::I64(data) => {
+                encode_data(&mut out, &name, metric.description(), data)
+            }
+        }
+    }
+    out
+}
+
+fn encode_data<T: Display + Copy>(
+    out: &mut String,
+    name: &str,
+    description: &str,
+    data: &MetricData<T>,
+) {
+    let help = |out: &mut String, name: &str, kind: &str| {
+        if !description.is_empty() {
+            let _ = writeln!(out, "# HELP {name} {}", description.replace('\n', " "));
+        }
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+    };
+
+    match data {
+        MetricData::Gauge(gauge) => {
+            help(out, name, "gauge");
+            for point in gauge.data_points() {
+                sample(out, name, point.attributes(), None, point.value());
+            }
+        }
+        MetricData::Sum(sum) if sum.is_monotonic() => {
+            let name = format!("{name}_total");
+            help(out, &name, "counter");
+            for point in sum.data_points() {
+                sample(out, &name, point.attributes(), None, point.value());
+            }
+        }
+        MetricData::Sum(sum) => {
+            help(out, name, "gauge");
+            for point in sum.data_points() {
+                sample(out, name, point.attributes(), None, point.value());
+            }
+        }
+        MetricData::Histogram(histogram) => {
+            help(out, name, "histogram");
+            let bucket = format!("{name}_bucket");
+            for point in histogram.data_points() {
+                let mut count = 0;
+                let bounds = point.bounds().map(|b| b.to_string());
+                for (le, n) in bounds
+                    .chain(std::iter::once("+Inf".into()))
+                    .zip(point.bucket_counts())
+                {
+                    count += n;
+                    let le = ("le", le.as_str());
+                    sample(out, &bucket, point.attributes(), Some(le), count);
+                }
+                sample(
+                    out,
+                    &format!("{name}_sum"),
+                    point.attributes(),
+                    None,
+                    point.sum(),
+                );
+                sample(
+                    out,
+                    &format!("{name}_count"),
+                    point.attributes(),
+                    None,
+                    point.count(),
+                );
+            }
+        }
+        // Not produced by any of our instruments
+        MetricData::ExponentialHistogram(_) => {}
+    }
+}
+
+/// Write a single sample line
+fn sample<'a>(
+    out: &mut String,
+    name: &str,
+    attributes: impl Iterator<Item = &'a KeyValue>,
+    extra: Option<(&str, &str)>,
+    value: impl Display,
+) {
+    let mut labels = attributes
+        .map(|kv| (sanitize(kv.key.as_str()), kv.value.to_string()))
+        .chain(extra.map(|(k, v)| (k.to_string(), v.to_string())))
+        .map(|(k, v)| format!("{k}=\"{}\"", escape(&v)))
+        .collect::<Vec<_>>()
+        .join(",");
+    if !labels.is_empty() {
+        labels = format!("{{{labels}}}");
+    }
+    let _ = writeln!(out, "{name}{labels} {value}");
+}
+
+/// Replace characters that are not valid in prometheus names
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
@@ -0,0 +1,56 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use nomad_types::primitives::B256;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::types::{SignalStatus, SignalUpdate};
+
+/// Maximum number of signal statuses to remember
+const MAX_TRACKED_SIGNALS: usize = 65536;
+
+/// Bounded map of the last known status for each signal id, evicting the oldest signals first
+#[derive(Clone, Default)]
+pub(crate) struct StatusMap {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    statuses: HashMap<B256, SignalStatus>,
+    /// Signal ids in insertion order
+    order: VecDeque<B256>,
+}
+
+impl StatusMap {
+    /// Set the status of a signal
+    pub fn insert(&self, id: B256, status: SignalStatus) {
+        let inner = &mut *self.inner.lock().unwrap();
+        if inner.statuses.insert(id, status).is_none() {
+            inner.order.push_back(id);
+            if inner.order.len() > MAX_TRACKED_SIGNALS {
+                if let Some(oldest) = inner.order.pop_front() {
+                    inner.statuses.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Get the last known status of a signal
+    pub fn get(&self, id: &B256) -> Option<SignalStatus> {
+        self.inner.lock().unwrap().statuses.get(id).cloned()
+    }
+
+    /// Record status updates from the node until the channel closes
+    pub async fn track(self, mut updates: Receiver<SignalUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => self.insert(update.signal.id(), update.status),
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
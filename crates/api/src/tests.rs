@@ -0,0 +1,425 @@
+use futures::{SinkExt, StreamExt};
+use nomad_pool::SignalPool;
+use nomad_types::{primitives::U256, EncryptedSignal, Signal};
+use opentelemetry::{metrics::MeterProvider, KeyValue};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use tokio::sync::{broadcast, mpsc::unbounded_channel};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    prometheus::PrometheusReader,
+    spawn_api_server,
+    types::{PoolStatus, SignalAccepted, SignalRequest, SignalStatus, SignalUpdate},
+    ApiConfig, BindConfig, RouteSet,
+};
+
+fn test_signal() -> Signal {
+    Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn admin_bind_only_serves_admin_routes() -> eyre::Result<()> {
+    let (signal_tx, _signal_rx) = unbounded_channel();
+    let config = ApiConfig {
+        port: 0,
+        routes: RouteSet::Public,
+        binds: vec![BindConfig {
+            address: "127.0.0.1:0".parse()?,
+            routes: RouteSet::Admin,
+        }],
+        ..Default::default()
+    };
+    let addresses = spawn_api_server(
+        config,
+        true,
+        true,
+        signal_tx,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await?;
+    let (public, admin) = (addresses[0], addresses[1]);
+    assert!(admin.ip().is_loopback());
+
+    // Public routes are served on the public interface
+    let res = reqwest::get(format!("http://127.0.0.1:{}/health", public.port())).await?;
+    assert_eq!(res.status(), 200);
+
+    // but not on the admin interface
+    let res = reqwest::get(format!("http://{admin}/health")).await?;
+    assert_eq!(res.status(), 404);
+
+    // and vice versa for admin routes
+    let res = reqwest::get(format!("http://127.0.0.1:{}/pool", public.port())).await?;
+    assert_eq!(res.status(), 404);
+    let res = reqwest::get(format!("http://{admin}/pool")).await?;
+    assert_eq!(res.status(), 200);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bind_failures_are_returned() -> eyre::Result<()> {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let address = taken.local_addr()?;
+    let config = ApiConfig {
+        port: 0,
+        binds: vec![BindConfig {
+            address,
+            routes: RouteSet::Admin,
+        }],
+        ..Default::default()
+    };
+    let err = spawn_api_server(
+        config,
+        true,
+        true,
+        unbounded_channel().0,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains(&address.to_string()), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_encrypted_signals_are_rejected() -> eyre::Result<()> {
+    let (signal_tx, mut signal_rx) = unbounded_channel();
+    let config = ApiConfig {
+        port: 0,
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await?[0];
+    let url = format!("http://127.0.0.1:{}/signal", address.port());
+
+    // Relay is unreachable, so these must be rejected before it is queried
+    let signal = |puzzle_len, data_len| {
+        SignalRequest::Encrypted(EncryptedSignal {
+            token_contract: [1; 20].into(),
+            relay: "http://127.0.0.1:1/relay".parse().unwrap(),
+            puzzle: vec![0; puzzle_len].into(),
+            data: vec![0; data_len].into(),
+        })
+    };
+    let client = reqwest::Client::new();
+
+    let res = client.post(&url).json(&signal(499, 64)).send().await?;
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().await?,
+        "Signal puzzle must have at least 500 bytes"
+    );
+
+    let res = client.post(&url).json(&signal(500, 23)).send().await?;
+    assert_eq!(res.status(), 400);
+    assert!(res
+        .text()
+        .await?
+        .starts_with("Encrypted data is not big enough"));
+
+    let res = client.post(&url).json(&signal(500, 64)).send().await?;
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await?.starts_with("Invalid signal puzzle"));
+
+    assert!(signal_rx.try_recv().is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn websocket_streams_signal_status() -> eyre::Result<()> {
+    let (signal_tx, mut signal_rx) = unbounded_channel();
+    let (status_tx, _) = broadcast::channel(16);
+    let config = ApiConfig {
+        port: 0,
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        status_tx.clone(),
+        SignalPool::new(16),
+    )
+    .await?[0];
+    let url = format!("ws://127.0.0.1:{}/signal/ws", address.port());
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await?;
+
+    let req = SignalRequest::Unencrypted(test_signal());
+    socket
+        .send(Message::text(serde_json::to_string(&req)?))
+        .await?;
+
+    let mut next_status = async || -> eyre::Result<SignalStatus> {
+        let msg = socket.next().await.unwrap()?;
+        Ok(serde_json::from_str(msg.to_text()?)?)
+    };
+    assert_eq!(next_status().await?, SignalStatus::Received);
+
+    // Simulate the node processing the signal, alongside an unrelated one
+    let payload = signal_rx.recv().await.unwrap();
+    let other = SignalRequest::Encrypted(EncryptedSignal {
+        token_contract: [2; 20].into(),
+        relay: "http://127.0.0.1:1/relay".parse().unwrap(),
+        puzzle: vec![0; 500].into(),
+        data: vec![0; 32].into(),
+    })
+    .untraced();
+    for (signal, status) in [
+        (other, SignalStatus::Executed),
+        (payload.clone(), SignalStatus::Executing),
+        (payload, SignalStatus::Executed),
+    ] {
+        status_tx.send(SignalUpdate { signal, status })?;
+    }
+
+    assert_eq!(next_status().await?, SignalStatus::Executing);
+    assert_eq!(next_status().await?, SignalStatus::Executed);
+    Ok(())
+}
+
+#[tokio::test]
+async fn pool_status() -> eyre::Result<()> {
+    let (signal_tx, _signal_rx) = unbounded_channel();
+    let signal_pool = SignalPool::new(16);
+    let config = ApiConfig {
+        port: 0,
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        broadcast::channel(16).0,
+        signal_pool.clone(),
+    )
+    .await?[0];
+
+    let signal = SignalRequest::Unencrypted(test_signal()).untraced();
+    signal_pool.insert(signal.clone()).await;
+    signal_pool.insert(signal).await;
+
+    let url = format!("http://127.0.0.1:{}/pool", address.port());
+    let status: PoolStatus = reqwest::get(url).await?.json().await?;
+    assert_eq!(status.size, 1);
+    assert_eq!(status.max_size, 16);
+    assert_eq!(status.total_inserted, 1);
+    assert_eq!(status.duplicates, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn signal_requires_auth_token() -> eyre::Result<()> {
+    let (signal_tx, mut signal_rx) = unbounded_channel();
+    let config = ApiConfig {
+        port: 0,
+        auth_token: Some("secret".into()),
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await?[0];
+    let base = format!("http://127.0.0.1:{}", address.port());
+    let req = SignalRequest::Unencrypted(test_signal());
+    let client = reqwest::Client::new();
+
+    // Health stays public
+    let res = client.get(format!("{base}/health")).send().await?;
+    assert_eq!(res.status(), 200);
+
+    // Signals are rejected without the token, or with the wrong one
+    let res = client
+        .post(format!("{base}/signal"))
+        .json(&req)
+        .send()
+        .await?;
+    assert_eq!(res.status(), 401);
+    let res = client
+        .post(format!("{base}/signal"))
+        .bearer_auth("wrong")
+        .json(&req)
+        .send()
+        .await?;
+    assert_eq!(res.status(), 401);
+    assert!(signal_rx.try_recv().is_err());
+
+    // and accepted with it
+    let res = client
+        .post(format!("{base}/signal"))
+        .bearer_auth("secret")
+        .json(&req)
+        .send()
+        .await?;
+    assert_eq!(res.status(), 200);
+    assert!(signal_rx.try_recv().is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn prometheus_metrics() -> eyre::Result<()> {
+    let reader = PrometheusReader::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(reader.clone())
+        .build();
+    let meter = provider.meter("test");
+    let counter = meter
+        .u64_counter("signal_success")
+        .with_description("Number of successfully executed signals")
+        .build();
+    counter.add(2, &[KeyValue::new("reason", "a \"quoted\" reason")]);
+    meter.f64_gauge("balance").build().record(1.5, &[]);
+
+    let (signal_tx, _signal_rx) = unbounded_channel();
+    let config = ApiConfig {
+        port: 0,
+        prometheus: true,
+        prometheus_reader: Some(reader),
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await?[0];
+
+    let url = format!("http://127.0.0.1:{}/metrics", address.port());
+    let text = reqwest::get(&url).await?.text().await?;
+    for line in [
+        "# HELP signal_success_total Number of successfully executed signals",
+        "# TYPE signal_success_total counter",
+        r#"signal_success_total{reason="a \"quoted\" reason"} 2"#,
+        "# TYPE balance gauge",
+        "balance 1.5",
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing {line:?} in:\n{text}"
+        );
+    }
+
+    // Metrics are collected on each request
+    counter.add(1, &[KeyValue::new("reason", "a \"quoted\" reason")]);
+    let text = reqwest::get(url).await?.text().await?;
+    assert!(text.contains(r#"signal_success_total{reason="a \"quoted\" reason"} 3"#));
+    Ok(())
+}
+
+#[tokio::test]
+async fn signal_status_lookup() -> eyre::Result<()> {
+    let (signal_tx, mut signal_rx) = unbounded_channel();
+    let (status_tx, _) = broadcast::channel(16);
+    let config = ApiConfig {
+        port: 0,
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        status_tx.clone(),
+        SignalPool::new(16),
+    )
+    .await?[0];
+    let base = format!("http://127.0.0.1:{}", address.port());
+    let client = reqwest::Client::new();
+
+    let req = SignalRequest::Unencrypted(test_signal());
+    let accepted: SignalAccepted = client
+        .post(format!("{base}/signal"))
+        .header("trace_id", "0x00112233445566778899aabbccddeeff")
+        .json(&req)
+        .send()
+        .await?
+        .json()
+        .await?;
+    let payload = signal_rx.recv().await.unwrap();
+    assert_eq!(accepted.id, payload.id());
+    assert_eq!(accepted.id, req.untraced().id());
+
+    let url = format!("{base}/signal/{}", accepted.id);
+    let status: SignalStatus = client.get(&url).send().await?.json().await?;
+    assert_eq!(status, SignalStatus::Received);
+
+    // Updates from the node are reflected in the lookup
+    status_tx.send(SignalUpdate {
+        signal: payload,
+        status: SignalStatus::Executed,
+    })?;
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let status: SignalStatus = client.get(&url).send().await?.json().await?;
+    assert_eq!(status, SignalStatus::Executed);
+
+    let res = client
+        .get(format!(
+            "{base}/signal/{}",
+            nomad_types::primitives::B256::ZERO
+        ))
+        .send()
+        .await?;
+    assert_eq!(res.status(), 404);
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_signals_are_rejected() -> eyre::Result<()> {
+    let (signal_tx, mut signal_rx) = unbounded_channel();
+    let config = ApiConfig {
+        port: 0,
+        ..Default::default()
+    };
+    let address = spawn_api_server(
+        config,
+        true,
+        false,
+        signal_tx,
+        broadcast::channel(16).0,
+        SignalPool::new(16),
+    )
+    .await?[0];
+    let url = format!("http://127.0.0.1:{}/signal", address.port());
+
+    let mut signal = test_signal();
+    signal.transfer_amount = U256::ZERO;
+    let res = reqwest::Client::new()
+        .post(&url)
+        .json(&SignalRequest::Unencrypted(signal))
+        .send()
+        .await?;
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().await?,
+        "Invalid signal: Transfer amount must be non-zero"
+    );
+    assert!(signal_rx.try_recv().is_err());
+    Ok(())
+}
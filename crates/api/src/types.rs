@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use nomad_types::{EncryptedSignal, Signal, SignalPayload};
+use nomad_types::{
+    primitives::B256, DryRunReport, EncryptedSignal, HexBytes, Signal, SignalPayload,
+};
 use utoipa::ToSchema;
 
 /// Encrypted or raw signal
@@ -51,9 +53,71 @@ pub struct HealthResponse {
     pub read_only: bool,
 }
 
+/// Signal pool status
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PoolStatus {
+    /// Number of signals currently queued
+    #[schema(example = 12)]
+    pub size: usize,
+    /// Maximum number of signals the pool will hold
+    #[schema(example = 65535)]
+    pub max_size: usize,
+    /// Total number of signals inserted since startup
+    #[schema(example = 420)]
+    pub total_inserted: u64,
+    /// Total number of duplicate signals rejected since startup
+    #[schema(example = 69)]
+    pub duplicates: u64,
+}
+
 /// Relay get response
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RelayGetResponse {
     pub status: String,
     pub service: String,
 }
+
+/// Response for an accepted signal
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SignalAccepted {
+    /// Signal id, for looking up the signal status
+    #[schema(value_type = HexBytes)]
+    pub id: B256,
+}
+
+/// Signal status, as streamed to websocket submitters and returned by status lookups
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SignalStatus {
+    /// Signal was validated and broadcast to the network
+    Received,
+    /// Node has started executing the signal
+    Executing,
+    /// Signal was successfully executed
+    Executed,
+    /// Signal was rejected or failed to execute
+    Failed {
+        #[schema(example = "decrypt_failed")]
+        reason: String,
+    },
+    /// Node is running in dry-run mode, and only estimated executing the signal
+    #[serde(rename = "dry_run")]
+    DryRun { estimate: DryRunReport },
+}
+
+impl SignalStatus {
+    /// Returns true if no further updates will follow this status
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            SignalStatus::Executed | SignalStatus::Failed { .. } | SignalStatus::DryRun { .. }
+        )
+    }
+}
+
+/// Status update for a signal processed by the node
+#[derive(Clone, Debug)]
+pub struct SignalUpdate {
+    pub signal: SignalPayload,
+    pub status: SignalStatus,
+}
@@ -0,0 +1,33 @@
+use alloy::{primitives::hex, signers::local::PrivateKeySigner};
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::Result;
+
+use nomad_node::config::Config;
+
+#[derive(Parser)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub cmd: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration, including cli key overrides, with secrets redacted
+    Show,
+}
+
+impl ConfigArgs {
+    pub async fn execute(self, mut config: Config, signers: Vec<PrivateKeySigner>) -> Result<()> {
+        match self.cmd {
+            ConfigCommand::Show => {
+                // Signers are built from the cli keys when given, otherwise from the config
+                config.private_keys = signers
+                    .iter()
+                    .map(|s| hex::encode_prefixed(s.to_bytes()))
+                    .collect();
+                print!("{}", toml::to_string_pretty(&config.redacted())?);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use clap::Parser;
+use color_eyre::eyre::{Context, Result};
+
+use nomad_types::{primitives::hex, EncryptedSignal};
+use nomad_vm::Program;
+
+#[derive(Parser)]
+pub struct DisassembleArgs {
+    /// Hex-encoded puzzle bytecode, or a json encrypted signal containing the puzzle
+    pub input: String,
+}
+
+impl DisassembleArgs {
+    pub fn execute(self) -> Result<()> {
+        let input = self.input.trim();
+        let bytecode = if input.starts_with('{') {
+            let signal: EncryptedSignal =
+                serde_json::from_str(input).context("failed to parse encrypted signal")?;
+            signal.puzzle.to_vec()
+        } else {
+            hex::decode(input).context("failed to decode hex bytecode")?
+        };
+
+        let program = Program::from_bytes(&bytecode)?;
+        println!("{} instructions, {} bytes", program.len(), bytecode.len());
+        print!("{}", program.disassemble());
+
+        Ok(())
+    }
+}
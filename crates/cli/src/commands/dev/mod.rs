@@ -8,8 +8,11 @@ use reqwest::Url;
 use nomad_ethereum::EthClient;
 use nomad_node::config::Config;
 
+mod disassemble;
 mod faucet;
 mod proof;
+mod quote;
+mod vm_run;
 
 /// RPC Client for local and remote nodes
 #[derive(Parser)]
@@ -26,6 +29,9 @@ impl Display for DevArgs {
         match self.cmd {
             DevCommand::Faucet(_) => f.write_str("dev_faucet"),
             DevCommand::Proof(_) => f.write_str("dev_proof"),
+            DevCommand::Disassemble(_) => f.write_str("dev_disassemble"),
+            DevCommand::Quote(_) => f.write_str("dev_quote"),
+            DevCommand::VmRun(_) => f.write_str("dev_vm_run"),
         }
     }
 }
@@ -36,6 +42,12 @@ pub enum DevCommand {
     Faucet(faucet::FaucetArgs),
     /// Generate proof for a transaction
     Proof(proof::ProofArgs),
+    /// Disassemble puzzle bytecode into human-readable mnemonics
+    Disassemble(disassemble::DisassembleArgs),
+    /// Quote how much ETH an amount of tokens would swap for
+    Quote(quote::QuoteArgs),
+    /// Execute puzzle bytecode in the vm and print the output registers
+    VmRun(vm_run::VmRunArgs),
 }
 
 impl DevArgs {
@@ -43,10 +55,17 @@ impl DevArgs {
         if let Some(rpc) = self.eth_rpc {
             config.eth.rpc = rpc;
         }
-        let client = EthClient::new(config.eth, signers).await?;
+        if let DevCommand::Quote(_) = self.cmd {
+            // Quotes only read from uniswap, so enable it regardless of swap settings
+            config.eth.uniswap.enabled = true;
+        }
+        let client = || EthClient::new(config.eth, signers);
         match self.cmd {
-            DevCommand::Faucet(args) => args.execute(client).await,
-            DevCommand::Proof(args) => args.execute(client).await,
+            DevCommand::Faucet(args) => args.execute(client().await?).await,
+            DevCommand::Proof(args) => args.execute(client().await?).await,
+            DevCommand::Disassemble(args) => args.execute(),
+            DevCommand::Quote(args) => args.execute(client().await?).await,
+            DevCommand::VmRun(args) => args.execute(config.vm.max_cycles),
         }
     }
 }
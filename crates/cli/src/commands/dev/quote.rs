@@ -0,0 +1,24 @@
+use alloy::primitives::{utils::format_ether, Address, U256};
+use clap::Parser;
+use color_eyre::Result;
+
+use nomad_ethereum::EthClient;
+
+#[derive(Parser)]
+pub struct QuoteArgs {
+    /// Token contract address
+    token: Address,
+    /// Amount of tokens to quote (in the token's smallest unit)
+    amount: U256,
+}
+
+impl QuoteArgs {
+    /// Quote how much ETH an amount of tokens would swap for on uniswap
+    pub async fn execute(self, eth_client: EthClient) -> Result<()> {
+        let eth = eth_client
+            .quote_token_to_eth(self.token, self.amount)
+            .await?;
+        println!("{} ETH", format_ether(eth));
+        Ok(())
+    }
+}
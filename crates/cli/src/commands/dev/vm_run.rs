@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use clap::Parser;
+use color_eyre::eyre::{Context, Result};
+
+use nomad_types::primitives::hex;
+use nomad_vm::{NomadVm, Program, VmOutput};
+
+#[derive(Parser)]
+pub struct VmRunArgs {
+    /// Hex-encoded bytecode, or a path to a file containing it
+    pub input: String,
+    /// Gas limit for execution, defaults to the configured vm limit
+    #[arg(long)]
+    pub max_cycles: Option<usize>,
+}
+
+impl VmRunArgs {
+    pub fn execute(self, max_cycles: usize) -> Result<()> {
+        let input = if Path::new(&self.input).is_file() {
+            std::fs::read_to_string(&self.input).context("failed to read bytecode file")?
+        } else {
+            self.input
+        };
+        let bytecode = hex::decode(input.trim()).context("failed to decode hex bytecode")?;
+        let program = Program::from_bytes(&bytecode)?;
+
+        let mut vm = NomadVm::new(self.max_cycles.unwrap_or(max_cycles));
+        let report = vm.execute_program_report(program)?;
+
+        println!("output: {}", hex::encode_prefixed(report.output));
+        println!("cycles: {}", report.cycles);
+        println!("gas: {}", report.gas);
+        if !report.halted {
+            println!("stopped at the gas limit before halting");
+        }
+        for (i, value) in VmOutput(report.output).registers().iter().enumerate() {
+            println!("R{i}: 0x{value:08X}");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::{net::SocketAddr, time::Duration};
+
+use alloy::signers::local::PrivateKeySigner;
+use clap::Parser;
+use color_eyre::eyre::{bail, Result};
+use reqwest::Url;
+use tokio::net::{TcpListener, UdpSocket};
+
+use nomad_node::config::Config;
+
+/// Timeout for rpc reachability checks
+const RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Parser)]
+pub struct DoctorArgs {}
+
+impl DoctorArgs {
+    pub async fn execute(self, config: Config, signers: Vec<PrivateKeySigner>) -> Result<()> {
+        let mut failures = 0;
+        let mut check = |name: &str, result: Result<String>| match result {
+            Ok(detail) => println!("[pass] {name}: {detail}"),
+            Err(e) => {
+                failures += 1;
+                println!("[fail] {name}: {e}");
+            }
+        };
+
+        check("configuration", config.validate().map(|_| "valid".into()));
+        check(
+            "signers",
+            Ok(match signers.len() {
+                0 => "none provided, the node will run read-only".into(),
+                n => format!("{n} accounts"),
+            }),
+        );
+        check("eth rpc", rpc_reachable(config.eth.rpc.clone()).await);
+
+        // Check that each listener's port is free
+        let tcp = std::iter::once(("api port", ([0, 0, 0, 0], config.api.port).into()))
+            .chain(config.api.binds.iter().map(|b| ("api bind", b.address)))
+            .chain(std::iter::once((
+                "p2p tcp port",
+                ([0, 0, 0, 0], config.p2p.tcp).into(),
+            )))
+            .collect::<Vec<(&str, SocketAddr)>>();
+        for (name, addr) in tcp.into_iter().filter(|(_, addr)| addr.port() != 0) {
+            let result = TcpListener::bind(addr).await;
+            check(
+                name,
+                result
+                    .map(|_| format!("{addr} is free"))
+                    .map_err(Into::into),
+            );
+        }
+        if let Some(port) = config.p2p.quic.filter(|&port| port != 0) {
+            let addr = SocketAddr::from(([0, 0, 0, 0], port));
+            let result = UdpSocket::bind(addr).await;
+            check(
+                "p2p quic port",
+                result
+                    .map(|_| format!("{addr} is free"))
+                    .map_err(Into::into),
+            );
+        }
+
+        if failures > 0 {
+            bail!("{failures} checks failed");
+        }
+        Ok(())
+    }
+}
+
+/// Check that the rpc responds to http requests, with any status
+async fn rpc_reachable(mut url: Url) -> Result<String> {
+    // Websocket endpoints also accept plain http requests
+    let scheme = match url.scheme() {
+        "ws" => Some("http"),
+        "wss" => Some("https"),
+        _ => None,
+    };
+    if let Some(scheme) = scheme {
+        let _ = url.set_scheme(scheme);
+    }
+    let host = url.host_str().unwrap_or_default().to_owned();
+
+    let res = reqwest::Client::builder()
+        .timeout(RPC_TIMEOUT)
+        .build()?
+        .head(url)
+        .send()
+        .await?;
+    Ok(format!("{host} responded with {}", res.status()))
+}
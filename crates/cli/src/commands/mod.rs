@@ -54,6 +54,10 @@ impl_command! {
     /// Development commands
     #[display = to_string]
     mod dev;
+    /// Inspect the node configuration
+    mod config;
+    /// Check the configuration, accounts, rpc, and ports before running the node
+    mod doctor;
 
     pub struct Command;
 }
@@ -23,6 +23,15 @@ pub struct RunArgs {
     /// Override Uniswap V2 router address
     #[arg(long)]
     pub uniswap_router: Option<Address>,
+    /// Select accounts and estimate gas for signals, without broadcasting any transactions
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Allow settings only meant for local development, such as a loopback eth rpc
+    #[arg(long)]
+    pub debug: bool,
+    /// Initialize the node and exit without processing any signals
+    #[arg(long)]
+    pub check: bool,
 }
 
 impl RunArgs {
@@ -44,7 +53,18 @@ impl RunArgs {
             config.eth.uniswap.router = router_address;
             info!("Using Uniswap router override: {}", router_address);
         }
+        if self.dry_run {
+            config.dry_run = true;
+        }
+        if self.debug {
+            config.debug = true;
+        }
 
-        NomadNode::init(config, signers).await?.run().await
+        let node = NomadNode::init(config, signers).await?;
+        if self.check {
+            info!("Node initialized successfully, exiting");
+            return Ok(());
+        }
+        node.run().await
     }
 }
@@ -33,22 +33,18 @@ impl WithdrawArgs {
         let eth_client = EthClient::new(config.eth, signers.clone()).await?;
         let provider = eth_client.wallet_provider().await?;
 
-        // Create token contract instance
-        let token = IERC20::new(self.token_contract, &provider);
-
         // Find a signer with sufficient balance
-        let mut selected_signer = None;
-        for signer in &signers {
-            let from_address = signer.address();
-            let balance = token.balanceOf(from_address).call().await?;
-
-            if balance >= U256::from(self.amount) {
-                selected_signer = Some(from_address);
+        let amount = U256::from(self.amount);
+        let token = IERC20::new(self.token_contract, &provider);
+        let mut source = None;
+        for (idx, signer) in signers.iter().enumerate() {
+            if token.balanceOf(signer.address()).call().await? >= amount {
+                source = Some(idx);
                 break;
             }
         }
 
-        let from_address = selected_signer.ok_or_else(|| {
+        let source = source.ok_or_else(|| {
             color_eyre::eyre::eyre!(
                 "No signer has sufficient balance. Required: {}, checked {} addresses",
                 self.amount,
@@ -59,17 +55,16 @@ impl WithdrawArgs {
         // Execute the transfer
         println!(
             "Transferring {} tokens from {} to {} using contract {}",
-            self.amount, from_address, self.to, self.token_contract
+            self.amount,
+            signers[source].address(),
+            self.to,
+            self.token_contract
         );
 
-        let tx = token
-            .transfer(self.to, U256::from(self.amount))
-            .from(from_address)
-            .send()
+        let receipt = eth_client
+            .redistribute(&provider, source, self.token_contract, self.to, amount)
             .await?;
 
-        let receipt = tx.get_receipt().await?;
-
         println!("Transfer successful!");
         println!("Transaction hash: {}", receipt.transaction_hash);
         println!("Block number: {}", receipt.block_number.unwrap_or_default());
@@ -15,13 +15,16 @@ use opentelemetry_sdk::{
 use opentelemetry_semantic_conventions::{resource::SERVICE_VERSION, SCHEMA_URL};
 use tracing::{info, trace};
 use tracing_subscriber::{
-    layer::SubscriberExt, registry, util::SubscriberInitExt, EnvFilter, Layer,
+    filter::Directive, layer::SubscriberExt, registry, util::SubscriberInitExt, EnvFilter, Layer,
 };
 use workspace_filter::workspace_filter;
 
+use nomad_api::prometheus::PrometheusReader;
 use nomad_node::config::Config;
 
 mod commands;
+#[cfg(test)]
+mod tests;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -40,6 +43,14 @@ pub(crate) struct Cli {
     #[arg(long, global = true, action(ArgAction::Append), display_order(0))]
     pub pk: Option<Vec<String>>,
 
+    /// Encrypted json keystore files to load ethereum private keys from
+    #[arg(long, global = true, action(ArgAction::Append), display_order(0))]
+    pub keystore: Option<Vec<PathBuf>>,
+
+    /// File containing the password for the keystore files
+    #[arg(long, global = true, display_order(0))]
+    pub password_file: Option<PathBuf>,
+
     /// Increases the level of verbosity. Max value is -vvvv.
     ///
     /// * Default: All crates at info level
@@ -51,6 +62,11 @@ pub(crate) struct Cli {
     #[clap(verbatim_doc_comment)]
     pub verbose: u8,
 
+    /// Additional log directives for specific targets, for example `nomad_p2p=trace`.
+    /// Ignored when `RUST_LOG` is set.
+    #[arg(long, global = true, action(ArgAction::Append), display_order(99))]
+    pub log_target: Vec<Directive>,
+
     #[command(subcommand)]
     pub cmd: commands::Command,
 }
@@ -58,8 +74,8 @@ pub(crate) struct Cli {
 impl Cli {
     /// Run the app
     async fn execute(self) -> Result<()> {
-        let config = Config::load(&self.config)?;
-        let (tracer, logger, meter) = self.setup_logging(&config).await?;
+        let mut config = Config::load(&self.config)?;
+        let (tracer, logger, meter) = self.setup_logging(&mut config).await?;
 
         let signers = self.build_signers(&config)?;
         self.cmd.execute(config, signers).await?;
@@ -77,32 +93,34 @@ impl Cli {
         Ok(())
     }
 
-    /// Build list of signers from the cli arguments and config
+    /// Build list of signers from the first available source, in order: cli keys,
+    /// keystore files, `NOMAD_PK_*` environment variables, and the config
     fn build_signers(&self, config: &Config) -> Result<Vec<PrivateKeySigner>> {
-        let keys = if let Some(cli_keys) = &self.pk {
-            // If CLI keys are provided, use only those
-            cli_keys.clone()
+        let signers = if let Some(cli_keys) = &self.pk {
+            parse_keys(cli_keys)?
+        } else if let Some(paths) = &self.keystore {
+            let Some(password_file) = &self.password_file else {
+                bail!("--password-file is required to decrypt keystores");
+            };
+            let password = std::fs::read_to_string(password_file)
+                .with_context(|| format!("failed to read password file {password_file:?}"))?;
+            decrypt_keystores(paths, password.trim_end_matches(['\r', '\n']))?
         } else {
-            // Otherwise, use config keys
-            config.private_keys.clone()
+            let env_keys = env_keys(std::env::vars())?;
+            if env_keys.is_empty() {
+                parse_keys(&config.private_keys)?
+            } else {
+                parse_keys(&env_keys)?
+            }
         };
 
-        if keys.is_empty() {
-            return Ok(vec![]);
-        }
-        if keys.len() < 2 {
+        if signers.len() == 1 {
             bail!("At least 2 ethereum keys are required");
         }
-
-        keys.iter()
-            .map(|s| {
-                s.parse::<PrivateKeySigner>()
-                    .inspect(|v| {
-                        info!("Using Ethereum Account: {}", v.address());
-                    })
-                    .with_context(|| format!("failed to parse key: {s}"))
-            })
-            .collect()
+        for signer in &signers {
+            info!("Using Ethereum Account: {}", signer.address());
+        }
+        Ok(signers)
     }
 
     /// Get global ip address
@@ -117,27 +135,38 @@ impl Cli {
         Ok(None)
     }
 
+    /// Build the console log filter from `RUST_LOG` if set, otherwise from the
+    /// verbosity flag and any target directives
+    fn console_filter(&self, rust_log: Option<String>) -> EnvFilter {
+        if let Some(filter) = rust_log {
+            return EnvFilter::builder().parse_lossy(filter);
+        }
+
+        // Default which is directed by the verbosity flag
+        let filter = match self.verbose {
+            0 => "info".into(),
+            1 => workspace_filter!("debug", "info,nomad={level}"),
+            2 => workspace_filter!("trace", "info,nomad={level}"),
+            3 => workspace_filter!("trace", "debug,nomad={level}"),
+            _ => "trace".into(),
+        };
+        self.log_target.iter().cloned().fold(
+            EnvFilter::builder().parse_lossy(filter),
+            |filter, directive| filter.add_directive(directive),
+        )
+    }
+
     // Setup logging filters and subscriber
     pub async fn setup_logging(
         &self,
-        config: &Config,
+        config: &mut Config,
     ) -> Result<(
         Option<SdkTracerProvider>,
         Option<SdkLoggerProvider>,
         Option<SdkMeterProvider>,
     )> {
         // Setup console logging
-        let filter = std::env::var("RUST_LOG").unwrap_or_else(|_| {
-            // Default which is directed by the verbosity flag
-            match self.verbose {
-                0 => "info".into(),
-                1 => workspace_filter!("debug", "info,nomad={level}"),
-                2 => workspace_filter!("trace", "info,nomad={level}"),
-                3 => workspace_filter!("trace", "debug,nomad={level}"),
-                _ => "trace".into(),
-            }
-        });
-        let filter = EnvFilter::builder().parse_lossy(filter);
+        let filter = self.console_filter(std::env::var("RUST_LOG").ok());
         let env_filter = filter.to_string();
         let console = tracing_subscriber::fmt::layer()
             .with_target(self.verbose > 2)
@@ -156,7 +185,7 @@ impl Cli {
         let mut meter = None;
 
         // setup telemetry if enabled
-        if config.otlp.logs || config.otlp.metrics || config.otlp.traces {
+        if config.otlp.logs || config.otlp.metrics || config.otlp.traces || config.api.prometheus {
             // Create a Resource that captures information about the entity for which telemetry is recorded.
             let mut resource = Resource::builder()
                 .with_service_name(env!("CARGO_BIN_NAME"))
@@ -206,13 +235,20 @@ impl Cli {
                 tracer = Some(provider);
             }
 
-            if config.otlp.metrics {
+            if config.otlp.metrics || config.api.prometheus {
                 // Setup opentelemetry metrics
-                let exporter = MetricExporter::builder().with_http().build()?;
-                let provider = SdkMeterProvider::builder()
-                    .with_periodic_exporter(exporter)
-                    .with_resource(resource)
-                    .build();
+                let mut builder = SdkMeterProvider::builder().with_resource(resource);
+                if config.otlp.metrics {
+                    let exporter = MetricExporter::builder().with_http().build()?;
+                    builder = builder.with_periodic_exporter(exporter);
+                }
+                if config.api.prometheus {
+                    // Collect metrics for the api to serve at /metrics
+                    let reader = PrometheusReader::default();
+                    builder = builder.with_reader(reader.clone());
+                    config.api.prometheus_reader = Some(reader);
+                }
+                let provider = builder.build();
                 opentelemetry::global::set_meter_provider(provider.clone());
                 meter = Some(provider);
             }
@@ -227,6 +263,43 @@ impl Cli {
     }
 }
 
+/// Parse hex encoded private keys
+fn parse_keys(keys: &[String]) -> Result<Vec<PrivateKeySigner>> {
+    keys.iter()
+        .map(|s| {
+            s.parse::<PrivateKeySigner>()
+                .with_context(|| format!("failed to parse key: {s}"))
+        })
+        .collect()
+}
+
+/// Decrypt signers from json keystore files sharing a password
+fn decrypt_keystores(paths: &[PathBuf], password: &str) -> Result<Vec<PrivateKeySigner>> {
+    paths
+        .iter()
+        .map(|path| {
+            PrivateKeySigner::decrypt_keystore(path, password)
+                .with_context(|| format!("failed to decrypt keystore {path:?}"))
+        })
+        .collect()
+}
+
+/// Collect keys from `NOMAD_PK_<n>` environment variables, ordered by their numeric suffix
+fn env_keys(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    for (name, key) in vars {
+        let Some(suffix) = name.strip_prefix("NOMAD_PK_") else {
+            continue;
+        };
+        let Ok(index) = suffix.parse::<u64>() else {
+            bail!("{name} must end with a number, such as NOMAD_PK_1");
+        };
+        keys.push((index, key));
+    }
+    keys.sort_by_key(|(index, _)| *index);
+    Ok(keys.into_iter().map(|(_, key)| key).collect())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{decrypt_keystores, env_keys, Cli};
+
+/// Keystore for 0x2c7536E3605D9C16a7a3D7b1898e529396a65c23, encrypted with the password `nomad`
+fn keystore_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/keystore.json")
+}
+
+#[test]
+fn decrypt_sample_keystore() {
+    let signers = decrypt_keystores(&[keystore_path()], "nomad").unwrap();
+    assert_eq!(
+        signers[0].address().to_string(),
+        "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+    );
+}
+
+#[test]
+fn decrypt_keystore_wrong_password() {
+    let err = decrypt_keystores(&[keystore_path()], "wrong").unwrap_err();
+    assert!(
+        err.to_string().contains("failed to decrypt keystore"),
+        "{err}"
+    );
+}
+
+#[test]
+fn env_keys_are_ordered_by_number() {
+    let vars = [
+        ("NOMAD_PK_10", "0x10"),
+        ("NOMAD_PK_2", "0x02"),
+        ("HOME", "/root"),
+        ("NOMAD_PK_1", "0x01"),
+    ]
+    .map(|(name, value)| (name.to_string(), value.to_string()));
+    assert_eq!(env_keys(vars).unwrap(), ["0x01", "0x02", "0x10"]);
+}
+
+#[test]
+fn env_keys_reject_non_numeric_suffix() {
+    let vars = [("NOMAD_PK_1", "0x01"), ("NOMAD_PK_BACKUP", "0x02")]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+    let err = env_keys(vars).unwrap_err();
+    assert!(err.to_string().contains("NOMAD_PK_BACKUP"), "{err}");
+}
+
+#[test]
+fn log_target_directives() {
+    let cli = Cli::try_parse_from([
+        "nomad",
+        "--log-target",
+        "nomad_p2p=trace",
+        "--log-target",
+        "libp2p_gossipsub=debug",
+        "config",
+        "show",
+    ])
+    .unwrap();
+    let filter = cli.console_filter(None).to_string();
+    assert!(filter.contains("nomad_p2p=trace"), "{filter}");
+    assert!(filter.contains("libp2p_gossipsub=debug"), "{filter}");
+
+    // RUST_LOG takes precedence over the flags
+    let filter = cli.console_filter(Some("warn".into())).to_string();
+    assert_eq!(filter, "warn");
+}
+
+#[test]
+fn invalid_log_target() {
+    assert!(
+        Cli::try_parse_from(["nomad", "--log-target", "nomad=loud", "config", "show"]).is_err()
+    );
+}
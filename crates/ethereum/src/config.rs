@@ -12,17 +12,26 @@ pub struct EthConfig {
     pub rpc: Url,
     /// Minimum eth required for an account to be usable
     pub min_eth: f64,
-    /// Uniswap V2 configuration
-    pub uniswap: UniswapV2Config,
+    /// Number of block confirmations to wait for before a transaction is considered final
+    pub confirmations: u64,
+    /// Bond posted to the escrow, in basis points of the signal reward
+    pub bond_percentage: u16,
+    /// Uniswap configuration
+    pub uniswap: UniswapConfig,
     /// Token swap configuration - table keyed by name
     pub token: HashMap<String, TokenConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(default)]
-pub struct UniswapV2Config {
+pub struct UniswapConfig {
     pub enabled: bool,
+    /// Protocol version to route swaps through
+    pub version: UniswapVersion,
+    /// Uniswap V2 router address
     pub router: Address,
+    /// Uniswap V3 configuration, used when `version` is `v3`
+    pub v3: UniswapV3Config,
     pub max_slippage_percent: u8,
     #[serde(with = "humantime_serde")]
     pub swap_deadline: Duration,
@@ -31,6 +40,25 @@ pub struct UniswapV2Config {
     pub check_interval: Duration,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UniswapVersion {
+    #[default]
+    V2,
+    V3,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UniswapV3Config {
+    /// SwapRouter address
+    pub router: Address,
+    /// QuoterV2 address, used to price swaps
+    pub quoter: Address,
+    /// Pool fee tier in hundredths of a bip (3000 = 0.3%)
+    pub fee: u32,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TokenConfig {
     pub address: Address,
@@ -65,16 +93,39 @@ impl Default for EthConfig {
         Self {
             rpc: "https://ethereum-rpc.publicnode.com".parse().unwrap(),
             min_eth: 0.01,
-            uniswap: UniswapV2Config::default(),
+            confirmations: 1,
+            bond_percentage: 5200,
+            uniswap: UniswapConfig::default(),
             token,
         }
     }
 }
 
-impl Default for UniswapV2Config {
+impl EthConfig {
+    /// Bond amount for a given reward, rounded down
+    pub fn bond_amount(&self, reward: U256) -> U256 {
+        // Split the reward to compute `reward * bps / 10000` without overflowing
+        let (bps, denominator) = (U256::from(self.bond_percentage), U256::from(10_000));
+        (reward / denominator).saturating_mul(bps) + (reward % denominator) * bps / denominator
+    }
+}
+
+impl UniswapConfig {
+    /// Router address for the configured protocol version
+    pub fn router_address(&self) -> Address {
+        match self.version {
+            UniswapVersion::V2 => self.router,
+            UniswapVersion::V3 => self.v3.router,
+        }
+    }
+}
+
+impl Default for UniswapConfig {
     fn default() -> Self {
         Self {
             enabled: false, // Disabled by default for safety
+            version: UniswapVersion::V2,
+            v3: UniswapV3Config::default(),
             router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"
                 .parse()
                 .unwrap(), // Mainnet router
@@ -85,3 +136,17 @@ impl Default for UniswapV2Config {
         }
     }
 }
+
+impl Default for UniswapV3Config {
+    fn default() -> Self {
+        Self {
+            router: "0xE592427A0AEce92De3Edee1F18E0157C05861564"
+                .parse()
+                .unwrap(), // Mainnet SwapRouter
+            quoter: "0x61fFE014bA17989E743c5F6cB21bF9697530B21e"
+                .parse()
+                .unwrap(), // Mainnet QuoterV2
+            fee: 3000,
+        }
+    }
+}
@@ -27,6 +27,61 @@ alloy::sol! {
         function factory() external pure returns (address);
     }
 
+    #[sol(rpc)]
+    contract ISwapRouter {
+        struct ExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint24 fee;
+            address recipient;
+            uint256 deadline;
+            uint256 amountIn;
+            uint256 amountOutMinimum;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        function exactInputSingle(ExactInputSingleParams calldata params)
+            external payable returns (uint256 amountOut);
+        function unwrapWETH9(uint256 amountMinimum, address recipient) external payable;
+        function multicall(bytes[] calldata data)
+            external payable returns (bytes[] memory results);
+        function WETH9() external view returns (address);
+    }
+
+    #[sol(rpc)]
+    contract IQuoterV2 {
+        struct QuoteExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint256 amountIn;
+            uint24 fee;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        struct QuoteExactOutputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint256 amount;
+            uint24 fee;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        function quoteExactInputSingle(QuoteExactInputSingleParams memory params)
+            external returns (
+                uint256 amountOut,
+                uint160 sqrtPriceX96After,
+                uint32 initializedTicksCrossed,
+                uint256 gasEstimate
+            );
+        function quoteExactOutputSingle(QuoteExactOutputSingleParams memory params)
+            external returns (
+                uint256 amountIn,
+                uint160 sqrtPriceX96After,
+                uint32 initializedTicksCrossed,
+                uint256 gasEstimate
+            );
+    }
+
     #[sol(rpc)]
     contract Escrow {
         #[derive(serde::Deserialize, serde::Serialize)]
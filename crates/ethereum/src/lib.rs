@@ -1,33 +1,44 @@
 use std::{fmt::Debug, time::Duration};
 
 use alloy::{
+    eips::BlockNumberOrTag,
     network::EthereumWallet,
     primitives::{
         utils::{format_ether, format_units, parse_ether},
-        Address, U256,
+        Address, Bytes, Selector, TxHash, U256,
     },
     providers::{
         fillers::{BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller},
         Identity, Provider, ProviderBuilder, RootProvider,
     },
-    rpc::types::TransactionReceipt,
+    rpc::{client::BatchRequest, types::TransactionReceipt},
     signers::local::PrivateKeySigner,
+    sol_types::SolCall,
     transports::{RpcError, TransportErrorKind},
 };
-use opentelemetry::{global::meter_provider, metrics::Gauge, KeyValue};
+use futures::future::try_join_all;
+use opentelemetry::{
+    global::meter_provider,
+    metrics::{Counter, Gauge, Meter},
+    KeyValue,
+};
 use otel_instrument::{instrument, tracer_name};
 use scc::HashMap;
 use tracing::{debug, info, warn};
 
-use nomad_types::{ObfuscatedCaller, Signal};
+use nomad_types::{
+    DryRunReport, SelectorMapping, Signal, ESCROW_BOND, ESCROW_COLLECT, ESCROW_IS_BONDED,
+};
 
 pub use crate::config::*;
-use crate::contracts::{Escrow, IUniswapV2Router02, IERC20};
+use crate::contracts::{Escrow, ISwapRouter, IUniswapV2Router02, IERC20};
 
 mod config;
 pub mod contracts;
 mod proof;
 mod swap;
+#[cfg(test)]
+mod tests;
 
 tracer_name!("nomad");
 
@@ -58,11 +69,38 @@ pub struct EthClient {
 pub struct BalanceMetrics {
     eth_balance: Gauge<f64>,
     token_balance: Gauge<f64>,
+    gas_spent: Counter<f64>,
+}
+
+impl BalanceMetrics {
+    /// Create balance and gas metrics on the given meter
+    pub fn new(meter: &Meter) -> Self {
+        let eth_balance = meter
+            .f64_gauge("eth_balance")
+            .with_description("ETH balance per account")
+            .build();
+
+        let token_balance = meter
+            .f64_gauge("token_balance")
+            .with_description("Token balance per account and token")
+            .build();
+
+        let gas_spent = meter
+            .f64_counter("gas_spent")
+            .with_description("ETH spent on gas per account")
+            .build();
+
+        Self {
+            eth_balance,
+            token_balance,
+            gas_spent,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct UniswapRuntime {
-    pub config: UniswapV2Config,
+    pub config: UniswapConfig,
     pub weth_address: Address,
     pub target_eth_wei: U256,
 }
@@ -79,12 +117,12 @@ pub enum ClientError {
     Proof(#[from] proof::ProofError),
     #[error("Contract already bonded")]
     AlreadyBonded,
+    #[error("Transaction {_0} reverted")]
+    Reverted(TxHash),
     #[error("Invalid contract bytecode")]
     InvalidBytecode,
     #[error("Read-only mode, no signers available")]
     ReadOnly,
-    #[error("Obfuscated Contract call failed: {_0}")]
-    ObfuscatedContractCall(String),
     #[error("Invalid selector mapping: {_0}")]
     InvalidSelectorMapping(String),
     #[error("Eth below minimum balance ({_0}) for the accounts: {_1:?}, need at least {_2} account funded")]
@@ -95,6 +133,10 @@ pub enum ClientError {
     SwapFailed(String),
     #[error("Insufficient token balance for swap: need {_0}, have {_1}")]
     InsufficientTokenBalance(U256, U256),
+    #[error("Account {_0} has insufficient token balance: need {_1}, have {_2}")]
+    InsufficientBalance(Address, U256, U256),
+    #[error("Uniswap is not enabled")]
+    UniswapDisabled,
 }
 
 impl EthClient {
@@ -126,9 +168,21 @@ impl EthClient {
         let uniswap = if config.uniswap.enabled {
             let target_wei = parse_ether(&config.uniswap.target_eth_amount.to_string()).unwrap();
 
-            // Get WETH address from router contract
-            let router = IUniswapV2Router02::new(config.uniswap.router, &read_provider);
-            let weth = router.WETH().call().await?;
+            // Get WETH address from the configured router contract
+            let weth = match config.uniswap.version {
+                UniswapVersion::V2 => {
+                    IUniswapV2Router02::new(config.uniswap.router, &read_provider)
+                        .WETH()
+                        .call()
+                        .await?
+                }
+                UniswapVersion::V3 => {
+                    ISwapRouter::new(config.uniswap.v3.router, &read_provider)
+                        .WETH9()
+                        .call()
+                        .await?
+                }
+            };
 
             Some(UniswapRuntime {
                 config: config.uniswap.clone(),
@@ -175,12 +229,15 @@ impl EthClient {
         for account in self.accounts.clone() {
             info!("Minting tokens for {account}");
             let res = token.mint().from(account).send().await?;
-            futs.push(res.watch());
+            futs.push(
+                res.with_required_confirmations(self.config.confirmations)
+                    .get_receipt(),
+            );
         }
 
         // Wait for all mint transactions to be verified
         for fut in futs {
-            fut.await?;
+            self.record_gas_spent(&fut.await?);
         }
 
         Ok(())
@@ -203,12 +260,9 @@ impl EthClient {
                 .validate_escrow_selectors()
                 .map_err(ClientError::InvalidSelectorMapping)?;
 
-            let caller = ObfuscatedCaller::new(selector_mapping.clone());
-
             // Check if contract is already bonded using obfuscated selector
-            let call_data = caller
-                .is_bonded_call_data()
-                .map_err(ClientError::ObfuscatedContractCall)?;
+            let call_data =
+                escrow_call_data(selector_mapping, ESCROW_IS_BONDED, Escrow::is_bondedCall {})?;
 
             let result = self
                 .read_provider
@@ -219,7 +273,8 @@ impl EthClient {
                 })
                 .await?;
 
-            if caller.parse_bool_result(&result) {
+            // Malformed results are treated as unbonded, and will fail when bonding instead
+            if Escrow::is_bondedCall::abi_decode_returns(&result).unwrap_or(false) {
                 return Err(ClientError::AlreadyBonded);
             }
 
@@ -264,13 +319,30 @@ impl EthClient {
         Ok(())
     }
 
+    /// Fetch the eth balance of each account in a single json-rpc batch request
+    pub async fn batch_balances(&self, accounts: &[Address]) -> Result<Vec<U256>, ClientError> {
+        if accounts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut batch = BatchRequest::new(self.read_provider.client());
+        let calls = accounts
+            .iter()
+            .map(|address| batch.add_call("eth_getBalance", &(address, BlockNumberOrTag::Latest)))
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.send().await?;
+        Ok(try_join_all(calls).await?)
+    }
+
     /// Get accounts above minimum eth balance, or return error if not at least 2
     #[instrument(skip_all, err)]
     async fn get_active_accounts(&self) -> Result<Vec<usize>, ClientError> {
+        let balances = self.batch_balances(&self.accounts).await?;
+
         let mut active = Vec::new();
         let mut inactive = Vec::new();
-        for (i, address) in self.accounts.iter().cloned().enumerate() {
-            if self.read_provider.get_balance(address).await? >= self.min_eth.0 {
+        for (i, balance) in balances.into_iter().enumerate() {
+            if balance >= self.min_eth.0 {
                 active.push(i);
             } else {
                 inactive.push(i);
@@ -295,13 +367,13 @@ impl EthClient {
     ) -> Result<Vec<(usize, U256)>, ClientError> {
         let contract = IERC20::new(contract, &self.read_provider);
 
-        let mut bals = Vec::new();
-        for idx in accounts {
+        // Fetch all balances concurrently
+        let contract = &contract;
+        try_join_all(accounts.iter().map(|idx| async move {
             let bal = contract.balanceOf(self.accounts[*idx]).call().await?;
-            bals.push((*idx, bal))
-        }
-
-        Ok(bals)
+            Ok((*idx, bal))
+        }))
+        .await
     }
 
     /// Select ideal accounts for EOA 1 and 2
@@ -317,12 +389,7 @@ impl EthClient {
             .await?;
 
         // Compute minimum bond amount
-        let bond_amount = signal
-            .reward_amount
-            .checked_mul(U256::from(52))
-            .unwrap()
-            .checked_div(U256::from(100))
-            .unwrap();
+        let bond_amount = self.config.bond_amount(signal.reward_amount);
 
         // Get the last used EOA 2 account for this token, if any
         let last_used_eoa_2 = self
@@ -372,12 +439,22 @@ impl EthClient {
         signal: Signal,
     ) -> Result<[TransactionReceipt; 2], ClientError> {
         // Compute minimum bond amount
-        let bond_amount = signal
-            .reward_amount
-            .checked_mul(U256::from(52))
-            .unwrap()
-            .checked_div(U256::from(100))
-            .unwrap();
+        let bond_amount = self.config.bond_amount(signal.reward_amount);
+
+        // Encode obfuscated bond calls up front, so a missing mapping can't leave a stuck approval
+        let bond_call_data = signal
+            .selector_mapping
+            .as_ref()
+            .map(|mapping| {
+                escrow_call_data(
+                    mapping,
+                    ESCROW_BOND,
+                    Escrow::bondCall {
+                        _bondAmount: bond_amount,
+                    },
+                )
+            })
+            .transpose()?;
 
         // Approve bond amount for escrow contract, on the token contract (always the same)
         let approve = IERC20::new(signal.token_contract, &provider)
@@ -385,8 +462,10 @@ impl EthClient {
             .from(self.accounts[eoa_1])
             .send()
             .await?
+            .with_required_confirmations(self.config.confirmations)
             .get_receipt()
             .await?;
+        self.record_gas_spent(&approve);
         opentelemetry::trace::get_active_span(|span| {
             span.set_attribute(KeyValue::new(
                 "tx_approve",
@@ -395,15 +474,10 @@ impl EthClient {
         });
 
         // Try to bond
-        let bond_result = if let Some(ref selector_mapping) = signal.selector_mapping {
+        let bond_result = if let Some(call_data) = bond_call_data {
             // Obfuscated contract - use raw call with obfuscated selector
             info!("Bonding to obfuscated escrow contract");
 
-            let caller = ObfuscatedCaller::new(selector_mapping.clone());
-            let call_data = caller
-                .bond_call_data(bond_amount)
-                .map_err(ClientError::ObfuscatedContractCall)?;
-
             provider
                 .send_transaction(alloy::rpc::types::TransactionRequest {
                     to: Some(alloy::primitives::TxKind::Call(signal.escrow_contract)),
@@ -412,6 +486,7 @@ impl EthClient {
                     ..Default::default()
                 })
                 .await?
+                .with_required_confirmations(self.config.confirmations)
                 .get_receipt()
                 .await
         } else {
@@ -429,13 +504,15 @@ impl EthClient {
                 .from(self.accounts[eoa_1])
                 .send()
                 .await?
+                .with_required_confirmations(self.config.confirmations)
                 .get_receipt()
                 .await
         };
 
         // If bond failed, revert approval to prevent stuck approvals
         match bond_result {
-            Ok(bond_receipt) => {
+            Ok(bond_receipt) if bond_receipt.status() => {
+                self.record_gas_spent(&bond_receipt);
                 opentelemetry::trace::get_active_span(|span| {
                     span.set_attribute(KeyValue::new(
                         "tx_bond",
@@ -445,20 +522,39 @@ impl EthClient {
                 info!("Successfully bonded to escrow");
                 Ok([approve, bond_receipt])
             }
-            Err(e) => {
+            res => {
+                // Reverted bonds still cost gas
+                let err = match res {
+                    Ok(bond_receipt) => {
+                        self.record_gas_spent(&bond_receipt);
+                        ClientError::Reverted(bond_receipt.transaction_hash)
+                    }
+                    Err(e) => e.into(),
+                };
                 warn!(
                     "Bond failed, reverting approval to prevent stuck tokens: {:?}",
-                    e
+                    err
                 );
 
                 // Reset approval to 0
-                let _ = IERC20::new(signal.token_contract, provider)
+                let reset = IERC20::new(signal.token_contract, provider)
                     .approve(signal.escrow_contract, U256::ZERO)
                     .from(self.accounts[eoa_1])
                     .send()
                     .await;
+                match reset {
+                    Ok(pending) => match pending
+                        .with_required_confirmations(self.config.confirmations)
+                        .get_receipt()
+                        .await
+                    {
+                        Ok(receipt) => self.record_gas_spent(&receipt),
+                        Err(e) => warn!("Failed to confirm approval reset: {e:?}"),
+                    },
+                    Err(e) => warn!("Failed to reset approval: {e:?}"),
+                }
 
-                Err(e.into())
+                Err(err)
             }
         }
     }
@@ -476,8 +572,10 @@ impl EthClient {
             .from(self.accounts[eoa_2])
             .send()
             .await?
+            .with_required_confirmations(self.config.confirmations)
             .get_receipt()
             .await?;
+        self.record_gas_spent(&receipt);
         opentelemetry::trace::get_active_span(|span| {
             span.set_attribute(KeyValue::new(
                 "tx_transfer",
@@ -487,6 +585,71 @@ impl EthClient {
         Ok(receipt)
     }
 
+    /// Estimate the transactions for executing a signal with the selected accounts,
+    /// without broadcasting anything.
+    ///
+    /// Bonding and collecting depend on the approval and transfer being mined first,
+    /// so only the approval and transfer can be estimated up front.
+    #[instrument(skip_all, err)]
+    pub async fn estimate_execution(
+        &self,
+        eoa_1: usize,
+        eoa_2: usize,
+        signal: &Signal,
+    ) -> Result<DryRunReport, ClientError> {
+        let bond_amount = self.config.bond_amount(signal.reward_amount);
+        let token = IERC20::new(signal.token_contract, &self.read_provider);
+        let approve_gas = token
+            .approve(signal.escrow_contract, bond_amount)
+            .from(self.accounts[eoa_1])
+            .estimate_gas()
+            .await?;
+        let transfer_gas = token
+            .transfer(signal.recipient, signal.transfer_amount)
+            .from(self.accounts[eoa_2])
+            .estimate_gas()
+            .await?;
+
+        Ok(DryRunReport {
+            bond_account: self.accounts[eoa_1],
+            transfer_account: self.accounts[eoa_2],
+            bond_amount,
+            approve_gas,
+            transfer_gas,
+        })
+    }
+
+    /// Redistribute tokens from one of the node's accounts to a target address
+    #[instrument(skip_all, fields(source = self.accounts[source]), err)]
+    pub async fn redistribute(
+        &self,
+        provider: impl Provider,
+        source: usize,
+        token: Address,
+        target: Address,
+        amount: U256,
+    ) -> Result<TransactionReceipt, ClientError> {
+        let source = self.accounts[source];
+        let token = IERC20::new(token, provider);
+
+        let balance = token.balanceOf(source).call().await?;
+        if balance < amount {
+            return Err(ClientError::InsufficientBalance(source, amount, balance));
+        }
+
+        let receipt = token
+            .transfer(target, amount)
+            .from(source)
+            .send()
+            .await?
+            .with_required_confirmations(self.config.confirmations)
+            .get_receipt()
+            .await?;
+        self.record_gas_spent(&receipt);
+        info!("Redistributed {amount} tokens to {target}");
+        Ok(receipt)
+    }
+
     /// Collect a reward by submitting proof for a signal
     #[instrument(skip_all, fields(eoa_1 = self.accounts[eoa_1]), err)]
     pub async fn collect(
@@ -501,10 +664,14 @@ impl EthClient {
             // Obfuscated contract - use raw call with obfuscated selector
             info!("Collecting from obfuscated escrow contract");
 
-            let caller = ObfuscatedCaller::new(selector_mapping.clone());
-            let call_data = caller
-                .collect_call_data()
-                .map_err(ClientError::ObfuscatedContractCall)?;
+            let call_data = escrow_call_data(
+                selector_mapping,
+                ESCROW_COLLECT,
+                Escrow::collectCall {
+                    proof,
+                    targetBlockNumber: U256::from(block),
+                },
+            )?;
 
             let receipt = provider
                 .send_transaction(alloy::rpc::types::TransactionRequest {
@@ -514,6 +681,7 @@ impl EthClient {
                     ..Default::default()
                 })
                 .await?
+                .with_required_confirmations(self.config.confirmations)
                 .get_receipt()
                 .await?;
             info!("Successfully collected from obfuscated escrow");
@@ -525,11 +693,13 @@ impl EthClient {
                 .from(self.accounts[eoa_1])
                 .send()
                 .await?
+                .with_required_confirmations(self.config.confirmations)
                 .get_receipt()
                 .await?;
             info!("Successfully collected from escrow");
             receipt
         };
+        self.record_gas_spent(&receipt);
 
         opentelemetry::trace::get_active_span(|span| {
             span.set_attribute(KeyValue::new(
@@ -542,22 +712,21 @@ impl EthClient {
 
     /// Enable balance metrics for monitoring (only used when running the node)
     pub async fn enable_balance_metrics(&mut self) {
-        let meter = meter_provider().meter("nomad");
-
-        let eth_balance = meter
-            .f64_gauge("eth_balance")
-            .with_description("ETH balance per account")
-            .build();
+        self.balance_metrics = Some(BalanceMetrics::new(&meter_provider().meter("nomad")));
+    }
 
-        let token_balance = meter
-            .f64_gauge("token_balance")
-            .with_description("Token balance per account and token")
-            .build();
+    /// Record the gas cost of a transaction against its sender (if metrics are enabled)
+    fn record_gas_spent(&self, receipt: &TransactionReceipt) {
+        let Some(ref metrics) = self.balance_metrics else {
+            return;
+        };
 
-        self.balance_metrics = Some(BalanceMetrics {
-            eth_balance,
-            token_balance,
-        });
+        let cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        let cost_eth: f64 = format_ether(cost).parse().unwrap_or(0.0);
+        metrics.gas_spent.add(
+            cost_eth,
+            &[KeyValue::new("account", receipt.from.to_string())],
+        );
     }
 
     /// Report current balances to OpenTelemetry metrics (if enabled)
@@ -611,3 +780,23 @@ impl EthClient {
         Ok(())
     }
 }
+
+/// Encode an escrow call for an obfuscated contract, replacing its selector with the
+/// mapping for `original`.
+///
+/// Mappings are keyed by the selectors of the original escrow functions, which don't
+/// always match the bindings' abi (`collect()` vs `collect(ReceiptProof,uint256)`), so
+/// the key is given explicitly rather than read from the encoded call.
+pub(crate) fn escrow_call_data<C: SolCall>(
+    mapping: &SelectorMapping,
+    original: Selector,
+    call: C,
+) -> Result<Bytes, ClientError> {
+    let selector = mapping.remap(original.0).ok_or_else(|| {
+        let name = C::SIGNATURE.split('(').next().unwrap_or(C::SIGNATURE);
+        ClientError::InvalidSelectorMapping(format!("Missing {name} selector mapping"))
+    })?;
+    let mut data = call.abi_encode();
+    data[..4].copy_from_slice(&selector);
+    Ok(data.into())
+}
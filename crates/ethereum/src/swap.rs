@@ -2,8 +2,9 @@ use std::{collections::HashSet, time::Duration};
 
 use alloy::{
     primitives::{
+        aliases::{U160, U24},
         utils::{format_ether, format_units},
-        U256,
+        Address, U256,
     },
     providers::Provider,
     rpc::types::TransactionReceipt,
@@ -12,16 +13,92 @@ use eyre::bail;
 use tracing::{info, trace, warn};
 
 use crate::{
-    contracts::{IUniswapV2Router02, IERC20},
-    ClientError, EthClient,
+    contracts::{IQuoterV2, ISwapRouter, IUniswapV2Router02, IERC20},
+    ClientError, EthClient, UniswapRuntime, UniswapVersion,
 };
 
+impl UniswapRuntime {
+    /// Quote the amount of ETH received for swapping an exact amount of tokens
+    async fn quote_eth_out(
+        &self,
+        provider: impl Provider,
+        token: Address,
+        amount_in: U256,
+    ) -> Result<U256, ClientError> {
+        let amount_out = match self.config.version {
+            UniswapVersion::V2 => {
+                IUniswapV2Router02::new(self.config.router, provider)
+                    .getAmountsOut(amount_in, vec![token, self.weth_address])
+                    .call()
+                    .await?[1]
+            }
+            UniswapVersion::V3 => {
+                IQuoterV2::new(self.config.v3.quoter, provider)
+                    .quoteExactInputSingle(IQuoterV2::QuoteExactInputSingleParams {
+                        tokenIn: token,
+                        tokenOut: self.weth_address,
+                        amountIn: amount_in,
+                        fee: U24::from(self.config.v3.fee),
+                        sqrtPriceLimitX96: U160::ZERO,
+                    })
+                    .call()
+                    .await?
+                    .amountOut
+            }
+        };
+        Ok(amount_out)
+    }
+
+    /// Quote the amount of tokens needed to receive an exact amount of ETH
+    async fn quote_tokens_in(
+        &self,
+        provider: impl Provider,
+        token: Address,
+        amount_out: U256,
+    ) -> Result<U256, ClientError> {
+        let amount_in = match self.config.version {
+            UniswapVersion::V2 => {
+                IUniswapV2Router02::new(self.config.router, provider)
+                    .getAmountsIn(amount_out, vec![token, self.weth_address])
+                    .call()
+                    .await?[0]
+            }
+            UniswapVersion::V3 => {
+                IQuoterV2::new(self.config.v3.quoter, provider)
+                    .quoteExactOutputSingle(IQuoterV2::QuoteExactOutputSingleParams {
+                        tokenIn: token,
+                        tokenOut: self.weth_address,
+                        amount: amount_out,
+                        fee: U24::from(self.config.v3.fee),
+                        sqrtPriceLimitX96: U160::ZERO,
+                    })
+                    .call()
+                    .await?
+                    .amountIn
+            }
+        };
+        Ok(amount_in)
+    }
+}
+
 impl EthClient {
     /// Get the swap check interval, returns None if Uniswap is disabled
     pub fn swap_check_interval(&self) -> Option<Duration> {
         self.uniswap.as_ref().map(|u| u.config.check_interval)
     }
 
+    /// Quote the amount of ETH received for swapping an amount of tokens
+    pub async fn quote_token_to_eth(
+        &self,
+        token: Address,
+        amount: U256,
+    ) -> Result<U256, ClientError> {
+        let uniswap = self.uniswap.as_ref().ok_or(ClientError::UniswapDisabled)?;
+        uniswap
+            .quote_eth_out(&self.read_provider, token, amount)
+            .await
+    }
+
     /// Check which accounts need ETH and have swappable tokens
     async fn check_swap_conditions(&self) -> Result<Vec<(usize, String, U256, U256)>, ClientError> {
         // Early return if Uniswap is not configured
@@ -69,17 +146,14 @@ impl EthClient {
 
                 let available_tokens = token_balance - token_config.min_balance;
 
-                // Get rough estimate of tokens needed for target ETH amount
-                // (We'll do exact calculation in swap_tokens_for_eth)
-                let router = IUniswapV2Router02::new(uniswap.config.router, &self.read_provider);
-                let path = vec![token_config.address, uniswap.weth_address];
-
                 // Try to get quote for available tokens to see if we can get enough ETH
-                let Ok(amounts_out) = router.getAmountsOut(available_tokens, path).call().await
+                // (We'll do exact calculation in swap_tokens_for_eth)
+                let Ok(estimated_eth_output) = uniswap
+                    .quote_eth_out(&self.read_provider, token_config.address, available_tokens)
+                    .await
                 else {
                     continue;
                 };
-                let estimated_eth_output = amounts_out[1];
 
                 // Only add to candidates if we can get at least the target ETH amount
                 if estimated_eth_output >= target_eth_to_get {
@@ -101,7 +175,7 @@ impl EthClient {
         Ok(swap_candidates)
     }
 
-    /// Execute token-to-ETH swap via Uniswap V2
+    /// Execute token-to-ETH swap via the configured Uniswap version
     async fn swap_tokens_for_eth(
         &self,
         provider: impl Provider,
@@ -122,17 +196,10 @@ impl EthClient {
 
         let account = self.accounts[account_idx];
 
-        // Get expected output amount from Uniswap
-        let router = IUniswapV2Router02::new(uniswap.config.router, &provider);
-
-        // Path: Token -> WETH -> ETH
-        let path = vec![token_config.address, uniswap.weth_address];
-
         // Calculate exact tokens needed for target ETH amount
-        let amount_to_swap = router
-            .getAmountsIn(target_eth_amount, path.clone())
-            .call()
-            .await?[0];
+        let amount_to_swap = uniswap
+            .quote_tokens_in(&provider, token_config.address, target_eth_amount)
+            .await?;
 
         // Verify we have enough tokens available
         if amount_to_swap > max_tokens_available {
@@ -165,33 +232,67 @@ impl EthClient {
         // First approve the router to spend tokens
 
         let approve_tx = token
-            .approve(uniswap.config.router, amount_to_swap)
+            .approve(uniswap.config.router_address(), amount_to_swap)
             .from(account)
             .send()
             .await?
+            .with_required_confirmations(self.config.confirmations)
             .get_receipt()
             .await?;
 
+        self.record_gas_spent(&approve_tx);
         info!(
             "Approved router to spend tokens: {}",
             approve_tx.transaction_hash
         );
 
         // Execute swap
-        let swap_tx = router
-            .swapExactTokensForETH(
-                amount_to_swap,
-                min_eth_out,
-                path,
-                account,
-                U256::from(deadline),
-            )
-            .from(account)
-            .send()
-            .await?
+        let pending = match uniswap.config.version {
+            UniswapVersion::V2 => {
+                // Path: Token -> WETH -> ETH
+                let path = vec![token_config.address, uniswap.weth_address];
+                IUniswapV2Router02::new(uniswap.config.router, &provider)
+                    .swapExactTokensForETH(
+                        amount_to_swap,
+                        min_eth_out,
+                        path,
+                        account,
+                        U256::from(deadline),
+                    )
+                    .from(account)
+                    .send()
+                    .await?
+            }
+            UniswapVersion::V3 => {
+                // Swap into WETH held by the router, then unwrap it to the account as ETH
+                let router = ISwapRouter::new(uniswap.config.v3.router, &provider);
+                let swap = router
+                    .exactInputSingle(ISwapRouter::ExactInputSingleParams {
+                        tokenIn: token_config.address,
+                        tokenOut: uniswap.weth_address,
+                        fee: U24::from(uniswap.config.v3.fee),
+                        recipient: Address::ZERO,
+                        deadline: U256::from(deadline),
+                        amountIn: amount_to_swap,
+                        amountOutMinimum: min_eth_out,
+                        sqrtPriceLimitX96: U160::ZERO,
+                    })
+                    .calldata()
+                    .clone();
+                let unwrap = router.unwrapWETH9(min_eth_out, account).calldata().clone();
+                router
+                    .multicall(vec![swap, unwrap])
+                    .from(account)
+                    .send()
+                    .await?
+            }
+        };
+        let swap_tx = pending
+            .with_required_confirmations(self.config.confirmations)
             .get_receipt()
             .await?;
 
+        self.record_gas_spent(&swap_tx);
         info!("Swap completed: {}", swap_tx.transaction_hash);
 
         Ok(swap_tx)
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use alloy::{
+    primitives::{fixed_bytes, utils::parse_ether, Address, Bytes, B256, U256},
+    providers::{mock::Asserter, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolValue},
+};
+use axum::{extract::State, routing::post, Json, Router};
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry_sdk::metrics::{
+    data::{AggregatedMetrics, MetricData},
+    InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+};
+use serde_json::{json, Value};
+
+use nomad_types::{SelectorMapping, Signal, ESCROW_BOND, ESCROW_COLLECT, ESCROW_IS_BONDED};
+
+use crate::{
+    contracts::Escrow, escrow_call_data, BalanceMetrics, ClientError, EthClient, EthConfig,
+};
+
+#[tokio::test]
+async fn redistribute_rejects_insufficient_balance() -> Result<(), ClientError> {
+    let client = EthClient::new(EthConfig::default(), vec![PrivateKeySigner::random()]).await?;
+
+    // Mock the balanceOf call for the source account
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+    asserter.push_success(&Bytes::from(U256::from(5).abi_encode()));
+
+    let err = client
+        .redistribute(
+            &provider,
+            0,
+            Address::repeat_byte(1),
+            Address::repeat_byte(2),
+            U256::from(10),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ClientError::InsufficientBalance(_, need, have)
+            if need == U256::from(10) && have == U256::from(5)
+    ));
+    Ok(())
+}
+
+#[test]
+fn escrow_call_data_requires_mapping() {
+    let mapping = SelectorMapping {
+        bond: fixed_bytes!("0x11223344"),
+        collect: fixed_bytes!("0x55667788"),
+        ..Default::default()
+    };
+
+    // Arguments are abi encoded after the translated selector
+    let call = Escrow::bondCall {
+        _bondAmount: U256::from(7),
+    };
+    let data = escrow_call_data(&mapping, ESCROW_BOND, call.clone()).unwrap();
+    assert_eq!(data[..4], [0x11, 0x22, 0x33, 0x44]);
+    assert_eq!(data[4..], call.abi_encode()[4..]);
+
+    // Collect is keyed on `collect()`, even though the call carries a proof
+    assert_ne!(Escrow::collectCall::SELECTOR, ESCROW_COLLECT.0);
+    let call = Escrow::collectCall {
+        proof: Escrow::ReceiptProof {
+            header: Bytes::from_static(&[1]),
+            receipt: Bytes::from_static(&[2]),
+            proof: Bytes::from_static(&[3]),
+            path: Bytes::from_static(&[4]),
+            log: U256::ZERO,
+        },
+        targetBlockNumber: U256::from(42),
+    };
+    let data = escrow_call_data(&mapping, ESCROW_COLLECT, call.clone()).unwrap();
+    assert_eq!(data[..4], [0x55, 0x66, 0x77, 0x88]);
+    assert_eq!(data[4..], call.abi_encode()[4..]);
+
+    let err = escrow_call_data(&mapping, ESCROW_IS_BONDED, Escrow::is_bondedCall {}).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid selector mapping: Missing is_bonded selector mapping"
+    );
+}
+
+#[test]
+fn bond_amount_from_percentage() {
+    let bond = |bond_percentage, reward: u64| {
+        EthConfig {
+            bond_percentage,
+            ..Default::default()
+        }
+        .bond_amount(U256::from(reward))
+    };
+
+    // Default of 52%
+    assert_eq!(
+        EthConfig::default().bond_amount(U256::from(100)),
+        U256::from(52)
+    );
+    assert_eq!(bond(5200, 1_000_000), U256::from(520_000));
+    // Rounds down
+    assert_eq!(bond(5200, 3), U256::from(1));
+    assert_eq!(bond(15000, 3), U256::from(4));
+    assert_eq!(bond(10000, 12345), U256::from(12345));
+    assert_eq!(bond(0, 12345), U256::ZERO);
+
+    // Large rewards don't overflow
+    let config = EthConfig::default();
+    assert_eq!(
+        config.bond_amount(U256::MAX),
+        U256::MAX / U256::from(10_000) * U256::from(5200)
+            + U256::MAX % U256::from(10_000) * U256::from(5200) / U256::from(10_000)
+    );
+}
+
+/// Minimal geth mock, answering from fixed account balances and nonces.
+/// Submitted transactions are all mined, except for the `revert`th one (from 0)
+#[derive(Default)]
+struct MockGeth {
+    accounts: HashMap<Address, U256>,
+    revert: Option<usize>,
+    senders: Mutex<Vec<Address>>,
+    requests: AtomicUsize,
+}
+
+/// Gas used by each mocked transaction, at 1 gwei
+const MOCK_GAS: u64 = 21_000;
+
+impl MockGeth {
+    fn respond(&self, req: &Value) -> Value {
+        let param = |i: usize| req["params"][i].clone();
+        let result = match req["method"].as_str().unwrap() {
+            "eth_blockNumber" => json!("0x0"),
+            "eth_getBalance" => {
+                let address: Address = serde_json::from_value(param(0)).unwrap();
+                json!(self.accounts[&address])
+            }
+            // Escrow is never bonded
+            "eth_call" => json!(Bytes::from(false.abi_encode())),
+            "eth_sendTransaction" => {
+                let from = serde_json::from_value(param(0)["from"].clone()).unwrap();
+                let mut senders = self.senders.lock().unwrap();
+                senders.push(from);
+                json!(B256::left_padding_from(&senders.len().to_be_bytes()))
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: B256 = serde_json::from_value(param(0)).unwrap();
+                let index = U256::from_be_bytes(hash.0).to::<usize>() - 1;
+                let from = self.senders.lock().unwrap()[index];
+                json!({
+                    "transactionHash": hash,
+                    "transactionIndex": "0x0",
+                    "blockHash": B256::repeat_byte(1),
+                    "blockNumber": "0x1",
+                    "from": from,
+                    "to": Address::repeat_byte(2),
+                    "cumulativeGasUsed": format!("{MOCK_GAS:#x}"),
+                    "gasUsed": format!("{MOCK_GAS:#x}"),
+                    "effectiveGasPrice": "0x3b9aca00",
+                    "contractAddress": null,
+                    "logs": [],
+                    "logsBloom": Bytes::from(vec![0; 256]),
+                    "type": "0x2",
+                    "status": if self.revert == Some(index) { "0x0" } else { "0x1" },
+                })
+            }
+            _ => Value::Null,
+        };
+        json!({ "jsonrpc": "2.0", "id": req["id"], "result": result })
+    }
+}
+
+/// Serve a geth mock over http, answering both single and batch requests
+async fn mock_geth(geth: MockGeth) -> (url::Url, Arc<MockGeth>) {
+    let geth = Arc::new(geth);
+    let app = Router::new()
+        .route(
+            "/",
+            post(
+                |State(geth): State<Arc<MockGeth>>, Json(req): Json<Value>| async move {
+                    geth.requests.fetch_add(1, Ordering::SeqCst);
+                    Json(match req {
+                        Value::Array(batch) => batch.iter().map(|req| geth.respond(req)).collect(),
+                        req => geth.respond(&req),
+                    })
+                },
+            ),
+        )
+        .with_state(geth.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let url = format!("http://{addr}").parse().unwrap();
+    (url, geth)
+}
+
+#[tokio::test]
+async fn balances_are_batched() -> Result<(), ClientError> {
+    let signers = vec![
+        PrivateKeySigner::random(),
+        PrivateKeySigner::random(),
+        PrivateKeySigner::random(),
+    ];
+    let addresses = signers.iter().map(|s| s.address()).collect::<Vec<_>>();
+    let (rpc, geth) = mock_geth(MockGeth {
+        accounts: HashMap::from([
+            (addresses[0], parse_ether("1").unwrap()),
+            (addresses[1], U256::ZERO),
+            (addresses[2], parse_ether("0.5").unwrap()),
+        ]),
+        ..Default::default()
+    })
+    .await;
+    let config = EthConfig {
+        rpc,
+        ..Default::default()
+    };
+    let client = EthClient::new(config, signers).await?;
+
+    // Account 1 is below the minimum balance
+    assert_eq!(client.get_active_accounts().await?, vec![0, 2]);
+    assert_eq!(geth.requests.load(Ordering::SeqCst), 1);
+
+    // Nothing to request without accounts
+    assert!(client.batch_balances(&[]).await?.is_empty());
+    assert_eq!(geth.requests.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gas_is_recorded_for_reverted_bonds() -> Result<(), ClientError> {
+    let signer = PrivateKeySigner::random();
+    let account = signer.address();
+    // Approve is mined, bond reverts, then the approval is reset
+    let (rpc, _geth) = mock_geth(MockGeth {
+        revert: Some(1),
+        ..Default::default()
+    })
+    .await;
+    let provider = ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .connect_http(rpc.clone());
+    let config = EthConfig {
+        rpc,
+        ..Default::default()
+    };
+    let mut client = EthClient::new(config, vec![signer, PrivateKeySigner::random()]).await?;
+
+    let exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    client.balance_metrics = Some(BalanceMetrics::new(&meter_provider.meter("test")));
+
+    let signal = Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: None,
+    };
+    let err = client.bond(&provider, 0, signal).await.unwrap_err();
+    assert!(matches!(err, ClientError::Reverted(_)), "{err:?}");
+
+    meter_provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let gas_spent = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .find(|m| m.name() == "gas_spent")
+        .unwrap();
+    let AggregatedMetrics::F64(MetricData::Sum(sum)) = gas_spent.data() else {
+        panic!("gas_spent should be an f64 sum");
+    };
+    let point = sum.data_points().next().unwrap();
+    assert_eq!(
+        point.attributes().next().unwrap().value.as_str(),
+        account.to_string()
+    );
+    // Approve, the reverted bond, and the approval reset
+    let expected = 3.0 * MOCK_GAS as f64 * 1e-9;
+    assert!(
+        (point.value() - expected).abs() < 1e-12,
+        "{}",
+        point.value()
+    );
+    Ok(())
+}
@@ -1,6 +1,11 @@
-use std::path::PathBuf;
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
 
 use eyre::{bail, Result};
+use reqwest::Url;
 use resolve_path::PathResolveExt;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
@@ -16,9 +21,14 @@ pub struct Config {
     pub p2p: P2pConfig,
     pub api: ApiConfig,
     pub vm: VmConfig,
+    pub pool: PoolConfig,
     pub eth: EthConfig,
     pub otlp: OtlpConfig,
     pub private_keys: Vec<String>,
+    /// Select accounts and estimate gas for signals, without broadcasting any transactions
+    pub dry_run: bool,
+    /// Allow settings only meant for local development, such as a loopback eth rpc
+    pub debug: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -35,6 +45,25 @@ impl Default for VmConfig {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// Maximum number of signals to hold before randomly discarding
+    pub max_size: usize,
+    /// Optional maximum age of signals before they are expired
+    #[serde(with = "humantime_serde")]
+    pub ttl: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 65535,
+            ttl: None,
+        }
+    }
+}
+
 /// Opentelemetry config, default with everything turned off
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -73,4 +102,69 @@ impl Config {
 
         Ok(config)
     }
+
+    /// Copy of the config with private keys and the api auth token redacted, safe to print
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        config.private_keys.fill("0x...redacted".into());
+        if config.api.auth_token.is_some() {
+            config.api.auth_token = Some("redacted".into());
+        }
+        config
+    }
+
+    /// Check the config for values that would prevent the node from running correctly
+    pub fn validate(&self) -> Result<()> {
+        // Check for tcp listeners sharing a port
+        let listeners = std::iter::once(("api", ([0, 0, 0, 0], self.api.port).into()))
+            .chain(self.api.binds.iter().map(|b| ("api bind", b.address)))
+            .chain(std::iter::once((
+                "p2p",
+                ([0, 0, 0, 0], self.p2p.tcp).into(),
+            )))
+            .collect::<Vec<(&str, SocketAddr)>>();
+        for (i, (a, addr_a)) in listeners.iter().enumerate() {
+            for (b, addr_b) in &listeners[i + 1..] {
+                if addr_a.port() != 0
+                    && addr_a.port() == addr_b.port()
+                    && (addr_a.ip() == addr_b.ip()
+                        || addr_a.ip().is_unspecified()
+                        || addr_b.ip().is_unspecified())
+                {
+                    bail!("Port collision: {a} ({addr_a}) and {b} ({addr_b})");
+                }
+            }
+        }
+
+        let rpc = &self.eth.rpc;
+        if !matches!(rpc.scheme(), "http" | "https" | "ws" | "wss") {
+            bail!("Unsupported eth rpc scheme: {}", rpc.scheme());
+        }
+        if rpc.host_str().is_none_or(str::is_empty) {
+            bail!("Eth rpc url is missing a host");
+        }
+        if !self.debug && is_loopback(rpc) {
+            bail!("Eth rpc url points to the local machine ({rpc}), which requires debug mode");
+        }
+
+        if self.pool.max_size == 0 {
+            bail!("Signal pool max size must be greater than zero");
+        }
+        if self.vm.max_cycles == 0 {
+            bail!("VM max cycles must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+/// Check if a url's host is the local machine
+fn is_loopback(url: &Url) -> bool {
+    url.host_str().is_some_and(|host| {
+        host.eq_ignore_ascii_case("localhost")
+            || host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    })
 }
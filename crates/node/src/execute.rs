@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use aes_gcm::{aead::AeadMutInPlace, KeyInit};
 use arrayref::array_ref;
 use chrono::Utc;
@@ -10,21 +12,46 @@ use opentelemetry::{
 use otel_instrument::instrument;
 use reqwest::Url;
 use sha3::Digest;
-use tracing::{error, info, warn};
-use zeroize::Zeroizing;
+use thiserror::Error;
+use tracing::{debug, error, info, warn};
 
 use nomad_ethereum::EthClient;
-use nomad_types::{ReceiptFormat, Signal, SignalPayload};
+use nomad_types::{signal_key, DryRunReport, ReceiptFormat, Signal, SignalPayload};
 use nomad_vm::VmSocket;
 
 use crate::_OTEL_TRACER_NAME;
 
+/// Errors from opening an encrypted signal. Decryption failures usually mean the
+/// submitter used the wrong key shares, while parse and validation failures mean
+/// the plaintext itself was malformed.
+#[derive(Debug, Error)]
+pub enum SignalError {
+    #[error("Failed to decrypt data: {0}")]
+    Decrypt(aes_gcm::Error),
+    #[error("Failed to decode signal: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Invalid signal: {0}")]
+    Invalid(#[from] nomad_types::SignalError),
+}
+
+impl SignalError {
+    /// Short reason code used for logs and metrics
+    pub fn reason(&self) -> &'static str {
+        match self {
+            SignalError::Decrypt(_) => "decrypt_failed",
+            SignalError::Parse(_) => "malformed_signal",
+            SignalError::Invalid(_) => "invalid_signal",
+        }
+    }
+}
+
 /// Wrapper around the implementation that optionally traces with the given trace id
 pub async fn execute_signal(
     signal: SignalPayload,
     eth_client: &EthClient,
     vm_socket: &VmSocket,
-) -> Result<()> {
+    dry_run: bool,
+) -> Result<Option<DryRunReport>> {
     // Initialize the span, optionally using the signal's trace id
     let tracer = global::tracer(_OTEL_TRACER_NAME);
     let mut builder = tracer.span_builder("execute_signal");
@@ -35,7 +62,7 @@ pub async fn execute_signal(
         .with_attributes([KeyValue::new("token", signal.token_contract().to_string())])
         .start(&tracer);
     async move {
-        execute_signal_impl(signal, eth_client, vm_socket)
+        execute_signal_impl(signal, eth_client, vm_socket, dry_run)
             .await
             .inspect_err(|e: &Report| {
                 // Mark span with errors if we have any
@@ -49,12 +76,16 @@ pub async fn execute_signal(
     .await
 }
 
-/// Process signals sampled from the pool
+/// Process signals sampled from the pool.
+///
+/// Dry runs stop after selecting accounts and estimating gas, without broadcasting
+/// any transactions, and return the estimate.
 pub async fn execute_signal_impl(
     signal: SignalPayload,
     eth_client: &EthClient,
     vm_socket: &VmSocket,
-) -> Result<()> {
+    dry_run: bool,
+) -> Result<Option<DryRunReport>> {
     let start_time = Utc::now().to_rfc3339();
     let signal = solve_and_decrypt_signal(vm_socket, signal).await?;
 
@@ -64,6 +95,12 @@ pub async fn execute_signal_impl(
     info!("Selecting active accounts");
     let [eoa_1, eoa_2] = eth_client.select_accounts(signal.clone()).await?;
 
+    if dry_run {
+        let report = eth_client.estimate_execution(eoa_1, eoa_2, &signal).await?;
+        info!(?report, "Dry run, not broadcasting transactions");
+        return Ok(Some(report));
+    }
+
     // Due to https://github.com/alloy-rs/alloy/issues/1318 continuing to poll in the
     // background, the provider holds onto the span and prevents sending to telemetry.
     // As a workaround, we only create a wallet provider while it's needed.
@@ -77,35 +114,42 @@ pub async fn execute_signal_impl(
         .transfer(&provider, eoa_2, signal.clone())
         .await?;
 
-    // Send receipt to client
+    let acknowledgement_url = signal.acknowledgement_url.clone();
+    let collect = async {
+        info!("Generating transfer proof");
+        let proof = eth_client.generate_proof(Some(&signal), &transfer).await?;
+
+        info!("Collecting rewards from escrow");
+        eth_client
+            .collect(
+                &provider,
+                eoa_1,
+                signal,
+                proof,
+                transfer.block_number.unwrap(),
+            )
+            .await
+    }
+    .await
+    .inspect_err(|_| error!("Executed transfer but failed to collect rewards and transfer funds"));
+
+    // Send receipt to client, even if collecting failed, since the recipient has been paid
     acknowledgement(
-        signal.acknowledgement_url.clone(),
+        acknowledgement_url,
         ReceiptFormat {
             start_time,
             end_time: Utc::now().to_rfc3339(),
             approval_transaction_hash: approve.transaction_hash.to_string(),
             bond_transaction_hash: bond.transaction_hash.to_string(),
             transfer_transaction_hash: transfer.transaction_hash.to_string(),
+            collect_transaction_hash: collect
+                .as_ref()
+                .ok()
+                .map(|receipt| receipt.transaction_hash.to_string()),
         },
     )
     .await?;
-
-    info!("Generating transfer proof");
-    let proof = eth_client.generate_proof(Some(&signal), &transfer).await?;
-
-    info!("Collecting rewards from escrow");
-    eth_client
-        .collect(
-            &provider,
-            eoa_1,
-            signal,
-            proof,
-            transfer.block_number.unwrap(),
-        )
-        .await
-        .inspect_err(|_| {
-            error!("Executed transfer but failed to collect rewards and transfer funds")
-        })?;
+    collect?;
 
     // Update balance metrics for both accounts used in the transaction
     if let Err(e) = eth_client
@@ -119,7 +163,7 @@ pub async fn execute_signal_impl(
     }
 
     info!("Successfully executed signal");
-    Ok(())
+    Ok(None)
 }
 
 /// Decrypt signal payloads into an executable request
@@ -140,7 +184,7 @@ async fn solve_and_decrypt_signal(vm_socket: &VmSocket, signal: SignalPayload) -
 
             info!("Executing puzzle in vm");
             let k2 = vm_socket
-                .run((signal.puzzle.to_vec(), Context::current()))
+                .run((signal.puzzle.to_vec(), None, Context::current()))
                 .await
                 .map_err(|e| eyre!("failed to receive puzzle response: {e}"))?
                 .context("failed to execute puzzle")?;
@@ -167,21 +211,14 @@ async fn solve_and_decrypt_signal(vm_socket: &VmSocket, signal: SignalPayload) -
             // The first 12 bytes in data contain the nonce
             let nonce_bytes = signal.data.split_to(12);
             // The rest of the payload is our ciphertext
-            let mut data = signal.data.split_to(signal.data.len()).to_vec();
-            // sort k1 and k2 to determine hashing order
-            let mut sorted_shares = [*array_ref![k1, 0, 32], k2];
-            sorted_shares.sort();
-            // Compute sha356(k1 . k2) for 256 bit encryption key
-            let key = Zeroizing::new(sha3::Sha3_256::digest(sorted_shares.as_flattened()));
-            // Decrypt signal with aes-gcm
-            aes_gcm::Aes256Gcm::new(&key)
-                .decrypt_in_place(array_ref![nonce_bytes, 0, 12].into(), &[], &mut data)
-                .map_err(|e| eyre!("Failed to decrypt data: {e}"))?;
-
-            info!("Parsing raw signal");
-            // TODO: consider supporting more encodings
-            let raw_signal: Signal =
-                serde_json::from_slice(&data).context("Failed to decode signal")?;
+            let data = signal.data.split_to(signal.data.len()).to_vec();
+            let raw_signal = decrypt_signal(
+                array_ref![nonce_bytes, 0, 12],
+                data,
+                array_ref![k1, 0, 32],
+                &k2,
+            )
+            .inspect_err(|e| warn!(reason = e.reason(), "Failed to open encrypted signal"))?;
             if raw_signal.token_contract != signal.token_contract {
                 warn!(
                     inner_token = ?raw_signal.token_contract,
@@ -193,13 +230,78 @@ async fn solve_and_decrypt_signal(vm_socket: &VmSocket, signal: SignalPayload) -
     }
 }
 
+/// Decrypt signal data with the relay and puzzle key shares and parse the plaintext
+pub(crate) fn decrypt_signal(
+    nonce: &[u8; 12],
+    mut data: Vec<u8>,
+    k1: &[u8; 32],
+    k2: &[u8; 32],
+) -> Result<Signal, SignalError> {
+    // Decrypt signal with aes-gcm, keyed by sha3_256(k1 . k2)
+    let key = signal_key(k1, k2);
+    aes_gcm::Aes256Gcm::new(key.as_ref().into())
+        .decrypt_in_place(nonce.into(), &[], &mut data)
+        .map_err(SignalError::Decrypt)?;
+
+    // TODO: consider supporting more encodings
+    let signal: Signal = serde_json::from_slice(&data)?;
+    signal.validate()?;
+    Ok(signal)
+}
+
+/// Number of attempts made to deliver a receipt
+const RECEIPT_ATTEMPTS: u32 = 3;
+/// Timeout for each receipt delivery attempt
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Delay before the first retry, doubling after each failed attempt
+const RECEIPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 /// Send acknowledgement receipt to the signal producer
 #[instrument(skip(receipt))]
 async fn acknowledgement(url: Url, receipt: ReceiptFormat) -> Result<()> {
-    let res = reqwest::Client::new().post(url).json(&receipt).send().await;
+    let client = reqwest::Client::builder()
+        .timeout(RECEIPT_TIMEOUT)
+        .build()?;
+    let res = send_receipt(
+        &client,
+        url,
+        &receipt,
+        RECEIPT_ATTEMPTS,
+        RECEIPT_RETRY_DELAY,
+    )
+    .await;
     match res {
         Err(error) => warn!(?error, "Failed to send receipt"),
         Ok(_) => info!("Receipt sent successfully"),
     }
     Ok(())
 }
+
+/// POST a receipt, retrying failed requests and error responses with exponential backoff
+pub(crate) async fn send_receipt(
+    client: &reqwest::Client,
+    url: Url,
+    receipt: &ReceiptFormat,
+    attempts: u32,
+    mut delay: Duration,
+) -> reqwest::Result<()> {
+    let mut attempt = 1;
+    loop {
+        let res = client
+            .post(url.clone())
+            .json(receipt)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status());
+        match res {
+            Ok(_) => return Ok(()),
+            Err(error) if attempt < attempts => {
+                debug!(?error, attempt, "Failed to send receipt, retrying");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
@@ -1,20 +1,26 @@
 use std::sync::{atomic::AtomicBool, Arc};
 
 use alloy::signers::local::PrivateKeySigner;
-use eyre::Result;
-use opentelemetry::{global::meter_provider, metrics::Counter};
+use eyre::{bail, Result};
+use opentelemetry::{global::meter_provider, metrics::Counter, KeyValue};
 use otel_instrument::tracer_name;
-use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::{broadcast, mpsc::unbounded_channel};
 use tracing::{error, info, warn};
 
-use nomad_api::spawn_api_server;
+use nomad_api::{
+    spawn_api_server,
+    types::{SignalStatus, SignalUpdate},
+};
 use nomad_ethereum::{ClientError, EthClient};
 use nomad_p2p::P2pNode;
 use nomad_pool::SignalPool;
+use nomad_types::{DryRunReport, SignalPayload};
 use nomad_vm::{NomadVm, VmSocket};
 
 pub mod config;
 mod execute;
+#[cfg(test)]
+mod tests;
 
 tracer_name!("nomad");
 
@@ -22,6 +28,8 @@ pub struct NomadNode {
     signal_pool: SignalPool,
     eth_client: EthClient,
     vm_socket: VmSocket,
+    status_tx: broadcast::Sender<SignalUpdate>,
+    dry_run: bool,
     success: Counter<u64>,
     failure: Counter<u64>,
 }
@@ -29,24 +37,40 @@ pub struct NomadNode {
 impl NomadNode {
     /// Initialize the node with p2p, an eth client, and a vm worker thread
     pub async fn init(config: config::Config, signers: Vec<PrivateKeySigner>) -> Result<Self> {
+        config.validate()?;
+
         // If we dont have two keys, don't process any signals
+        if signers.len() == 1 {
+            bail!("At least two signers are required to execute signals, or none to run read-only");
+        }
         let read_only = signers.is_empty();
         if read_only {
             warn!("No signers provided; running node in read-only mode!");
         }
+        if config.dry_run {
+            warn!("Running node in dry-run mode, no transactions will be broadcast!");
+        }
+
+        // Create shared signal pool
+        let signal_pool = match config.pool.ttl {
+            Some(ttl) => SignalPool::new_with_ttl(config.pool.max_size, ttl),
+            None => SignalPool::new(config.pool.max_size),
+        };
 
         // Spawn api server
         let (signal_tx, signal_rx) = unbounded_channel();
-        let _ = spawn_api_server(
+        let (status_tx, _) = broadcast::channel(1024);
+        spawn_api_server(
             config.api,
             config.p2p.bootstrap.is_empty(),
             read_only,
             signal_tx,
+            status_tx.clone(),
+            signal_pool.clone(),
         )
-        .await;
+        .await?;
 
-        // Create shared signal pool and spawn p2p server
-        let signal_pool = SignalPool::new(65535);
+        // Spawn p2p server
         let read_only = Arc::new(AtomicBool::new(read_only));
         P2pNode::new(config.p2p, signal_pool.clone(), read_only, Some(signal_rx))?.spawn();
 
@@ -61,19 +85,22 @@ impl NomadNode {
         let meter = meter_provider().meter("nomad");
         let up = meter.u64_gauge("up").with_description("Node is up").build();
         up.record(1, &[]);
+        signal_pool.install_metrics(&meter);
         let success = meter
             .u64_counter("signal_success")
             .with_description("Number of successfully executed signals")
             .build();
         let failure = meter
             .u64_counter("signal_failure")
-            .with_description("Number of failures when executing signals")
+            .with_description("Number of failures when executing signals, by reason")
             .build();
 
         Ok(Self {
             signal_pool,
             eth_client,
             vm_socket,
+            status_tx,
+            dry_run: config.dry_run,
             success,
             failure,
         })
@@ -121,15 +148,56 @@ impl NomadNode {
     /// Handle the next signal from the pool (blocking until one is available)
     pub async fn next(&self) -> Result<()> {
         let signal = self.signal_pool.sample().await;
-        execute::execute_signal(signal, &self.eth_client, &self.vm_socket)
-            .await
-            .inspect(|_| {
+        self.update_status(&signal, SignalStatus::Executing);
+        let res = execute::execute_signal(
+            signal.clone(),
+            &self.eth_client,
+            &self.vm_socket,
+            self.dry_run,
+        )
+        .await;
+        self.report_result(&signal, &res);
+        res.map(|_| ())
+    }
+
+    /// Record metrics and publish the final status for an executed signal
+    fn report_result(&self, signal: &SignalPayload, res: &Result<Option<DryRunReport>>) {
+        match res {
+            // Dry runs never execute the signal, so they are neither a success nor a failure
+            Ok(Some(estimate)) => self.update_status(
+                signal,
+                SignalStatus::DryRun {
+                    estimate: estimate.clone(),
+                },
+            ),
+            Ok(None) => {
                 info!("Successfully executed signal");
                 self.success.add(1, &[]);
-            })
-            .inspect_err(|e| {
+                self.update_status(signal, SignalStatus::Executed);
+            }
+            Err(e) => {
                 error!("Failed to execute signal: {e:#}");
-                self.failure.add(1, &[]);
-            })
+                let reason = e
+                    .downcast_ref::<execute::SignalError>()
+                    .map_or("execution_failed", |e| e.reason());
+                self.failure.add(1, &[KeyValue::new("reason", reason)]);
+                self.update_status(
+                    signal,
+                    SignalStatus::Failed {
+                        reason: reason.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Publish a status update for the signal, if anyone is listening
+    fn update_status(&self, signal: &SignalPayload, status: SignalStatus) {
+        if self.status_tx.receiver_count() > 0 {
+            let _ = self.status_tx.send(SignalUpdate {
+                signal: signal.clone(),
+                status,
+            });
+        }
     }
 }
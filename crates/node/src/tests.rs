@@ -0,0 +1,303 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use aes_gcm::{aead::AeadMutInPlace, Aes256Gcm, KeyInit};
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use nomad_api::{types::SignalStatus, BindConfig, RouteSet};
+use nomad_ethereum::{EthClient, EthConfig};
+use nomad_pool::SignalPool;
+use nomad_types::{
+    primitives::{Address, U256},
+    DryRunReport, EncryptedSignal, ReceiptFormat, Signal, SignalPayload,
+};
+use nomad_vm::NomadVm;
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use sha3::Digest;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    config::Config,
+    execute::{decrypt_signal, send_receipt, SignalError},
+    NomadNode,
+};
+
+const NONCE: [u8; 12] = [7; 12];
+
+/// Encrypt a payload the same way signal submitters do
+fn encrypt(k1: [u8; 32], k2: [u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    let mut shares = [k1, k2];
+    shares.sort();
+    let key = sha3::Sha3_256::digest(shares.as_flattened());
+    let mut data = plaintext.to_vec();
+    Aes256Gcm::new(&key)
+        .encrypt_in_place(&NONCE.into(), &[], &mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn decrypt_failure_with_wrong_key() {
+    let data = encrypt([1; 32], [2; 32], b"{}");
+    let err = decrypt_signal(&NONCE, data, &[1; 32], &[3; 32]).unwrap_err();
+    assert!(matches!(err, SignalError::Decrypt(_)));
+    assert_eq!(err.reason(), "decrypt_failed");
+}
+
+#[test]
+fn parse_failure_with_malformed_signal() {
+    let data = encrypt([1; 32], [2; 32], b"not a signal");
+    // Key share order doesn't matter
+    let err = decrypt_signal(&NONCE, data, &[2; 32], &[1; 32]).unwrap_err();
+    assert!(matches!(err, SignalError::Parse(_)));
+    assert_eq!(err.reason(), "malformed_signal");
+}
+
+#[test]
+fn invalid_decrypted_signal() {
+    let signal = Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::ZERO,
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    };
+    let data = encrypt([1; 32], [2; 32], &serde_json::to_vec(&signal).unwrap());
+    let err = decrypt_signal(&NONCE, data, &[1; 32], &[2; 32]).unwrap_err();
+    assert!(matches!(err, SignalError::Invalid(_)));
+    assert_eq!(err.reason(), "invalid_signal");
+}
+
+#[test]
+fn decrypt_encrypted_signal() {
+    let signal = Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    };
+    let encrypted = EncryptedSignal::encrypt(
+        &signal,
+        "https://relay.com".parse().unwrap(),
+        Default::default(),
+        &[1; 32],
+        &[2; 32],
+        NONCE,
+    );
+    let (nonce, data) = encrypted.data.split_at(12);
+    let decrypted =
+        decrypt_signal(nonce.try_into().unwrap(), data.to_vec(), &[1; 32], &[2; 32]).unwrap();
+    assert_eq!(decrypted, signal);
+}
+
+fn test_receipt() -> ReceiptFormat {
+    ReceiptFormat {
+        start_time: "2025-01-01T00:00:00+00:00".into(),
+        end_time: "2025-01-01T00:01:00+00:00".into(),
+        bond_transaction_hash: "0x01".into(),
+        approval_transaction_hash: "0x02".into(),
+        transfer_transaction_hash: "0x03".into(),
+        collect_transaction_hash: Some("0x04".into()),
+    }
+}
+
+type RelayerState = (Arc<AtomicU32>, mpsc::UnboundedSender<ReceiptFormat>);
+
+/// Spawn a relayer that fails the first `failures` requests, then forwards receipts
+async fn mock_relayer(failures: u32) -> (reqwest::Url, mpsc::UnboundedReceiver<ReceiptFormat>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let attempts = Arc::new(AtomicU32::new(0));
+    let app = Router::new()
+        .route(
+            "/receipt",
+            post(
+                move |State((attempts, tx)): State<RelayerState>,
+                      Json(receipt): Json<ReceiptFormat>| async move {
+                    if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    tx.send(receipt).unwrap();
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state((attempts, tx));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let url = format!("http://{addr}/receipt").parse().unwrap();
+    (url, rx)
+}
+
+#[tokio::test]
+async fn receipt_is_delivered_after_retry() {
+    let (url, mut rx) = mock_relayer(1).await;
+    let client = reqwest::Client::new();
+    send_receipt(&client, url, &test_receipt(), 3, Duration::from_millis(10))
+        .await
+        .unwrap();
+    let receipt = rx.recv().await.unwrap();
+    assert_eq!(receipt.collect_transaction_hash.as_deref(), Some("0x04"));
+    assert_eq!(receipt.transfer_transaction_hash, "0x03");
+}
+
+#[tokio::test]
+async fn receipt_delivery_gives_up() {
+    let (url, mut rx) = mock_relayer(u32::MAX).await;
+    let client = reqwest::Client::new();
+    let err = send_receipt(&client, url, &test_receipt(), 2, Duration::from_millis(10))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn config_rejects_port_collisions() {
+    let mut config = Config::default();
+    config.p2p.tcp = config.api.port;
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("Port collision"), "{err}");
+
+    // Binding a specific interface on the default api port collides with the wildcard bind
+    let mut config = Config::default();
+    config.api.binds.push(BindConfig {
+        address: ([127, 0, 0, 1], config.api.port).into(),
+        routes: RouteSet::Admin,
+    });
+    assert!(config.validate().is_err());
+
+    // Distinct ports, or distinct interfaces on the same port, are fine
+    config.api.binds = vec![
+        BindConfig {
+            address: ([127, 0, 0, 1], 8001).into(),
+            routes: RouteSet::Admin,
+        },
+        BindConfig {
+            address: ([10, 0, 0, 1], 8001).into(),
+            routes: RouteSet::Admin,
+        },
+    ];
+    config.validate().unwrap();
+}
+
+#[test]
+fn config_rejects_invalid_rpc() {
+    let mut config = Config::default();
+    config.eth.rpc = "file:///tmp/rpc".parse().unwrap();
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("scheme"), "{err}");
+}
+
+#[test]
+fn config_rejects_loopback_rpc() {
+    for rpc in [
+        "http://127.0.0.1:8545",
+        "ws://localhost:8546",
+        "http://[::1]:8545",
+    ] {
+        let mut config = Config::default();
+        config.eth.rpc = rpc.parse().unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("debug mode"), "{err}");
+
+        // Allowed for local development
+        config.debug = true;
+        config.validate().unwrap();
+    }
+}
+
+#[test]
+fn config_rejects_zero_limits() {
+    let mut config = Config::default();
+    config.pool.max_size = 0;
+    assert!(config.validate().is_err());
+
+    let mut config = Config::default();
+    config.vm.max_cycles = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn redacted_config_hides_secrets() {
+    let key = "0x0123456789012345678901234567890123456789012345678901234567890123";
+    let mut config = Config {
+        private_keys: vec![key.into(), key.into()],
+        ..Default::default()
+    };
+    config.api.auth_token = Some("my-secret-token".into());
+
+    let output = toml::to_string_pretty(&config.redacted()).unwrap();
+    assert!(!output.contains(&key[2..]), "{output}");
+    assert!(!output.contains("my-secret-token"), "{output}");
+    assert_eq!(output.matches("0x...redacted").count(), 2);
+}
+
+#[tokio::test]
+async fn dry_runs_report_the_estimate() -> eyre::Result<()> {
+    let exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let meter = meter_provider.meter("test");
+    let (status_tx, mut status_rx) = broadcast::channel(16);
+    let node = NomadNode {
+        signal_pool: SignalPool::new(16),
+        eth_client: EthClient::new(EthConfig::default(), vec![]).await?,
+        vm_socket: NomadVm::new(0).spawn(),
+        status_tx,
+        dry_run: true,
+        success: meter.u64_counter("signal_success").build(),
+        failure: meter.u64_counter("signal_failure").build(),
+    };
+
+    let signal = SignalPayload::Unencrypted(Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    });
+    let estimate = DryRunReport {
+        bond_account: Address::repeat_byte(1),
+        transfer_account: Address::repeat_byte(2),
+        bond_amount: U256::from(52),
+        approve_gas: 46_000,
+        transfer_gas: 51_000,
+    };
+    node.report_result(&signal, &Ok(Some(estimate.clone())));
+
+    let status = status_rx.try_recv()?.status;
+    assert!(status.is_final());
+    assert_eq!(serde_json::to_value(&status)?["status"], "dry_run");
+    assert_eq!(status, SignalStatus::DryRun { estimate });
+
+    // Dry runs are neither counted as successes or failures
+    meter_provider.force_flush()?;
+    let metrics = exporter.get_finished_metrics()?;
+    let names = metrics
+        .iter()
+        .flat_map(|rm| rm.scope_metrics())
+        .flat_map(|sm| sm.metrics())
+        .map(|m| m.name())
+        .collect::<Vec<_>>();
+    assert!(names.is_empty(), "{names:?}");
+    Ok(())
+}
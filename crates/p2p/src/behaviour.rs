@@ -5,11 +5,12 @@ use std::{
 };
 
 use libp2p::{
+    connection_limits::{self, ConnectionLimits},
     gossipsub, identify,
     identity::Keypair,
     kad::{self, store::MemoryStore},
-    ping,
-    swarm::{dummy, NetworkBehaviour, ToSwarm},
+    mdns, ping,
+    swarm::{behaviour::toggle::Toggle, dummy, NetworkBehaviour, ToSwarm},
     Multiaddr, PeerId, StreamProtocol,
 };
 use nomad_types::SignalPayload;
@@ -19,18 +20,31 @@ use crate::{shutdown::Shutdown, P2pConfig, MIRAGE_DISCOVERY_ID, MIRAGE_MESHSUB_I
 
 #[derive(NetworkBehaviour)]
 pub struct MirageBehavior {
+    pub limits: connection_limits::Behaviour,
     pub shutdown: Shutdown,
     pub signal: SignalBehavior,
     pub identify: identify::Behaviour,
     pub ping: ping::Behaviour,
     pub kad: kad::Behaviour<MemoryStore>,
     pub gossipsub: gossipsub::Behaviour,
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
 }
 
 impl MirageBehavior {
-    pub fn new(keypair: &Keypair, config: &P2pConfig) -> Self {
+    pub fn new(
+        keypair: &Keypair,
+        config: &P2pConfig,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let peer_id = PeerId::from_public_key(&keypair.public());
 
+        // Deny connections beyond the configured limits
+        let limits = connection_limits::Behaviour::new(
+            ConnectionLimits::default()
+                .with_max_established_incoming(config.limits.max_established_incoming)
+                .with_max_established_outgoing(config.limits.max_established_outgoing)
+                .with_max_pending_incoming(config.limits.max_pending)
+                .with_max_pending_outgoing(config.limits.max_pending),
+        );
         let shutdown = Shutdown::default();
         let signal = SignalBehavior::default();
         let ping = ping::Behaviour::default();
@@ -38,10 +52,13 @@ impl MirageBehavior {
         let identity_config = identify::Config::new("0.1.0".into(), keypair.public());
         let identify = identify::Behaviour::new(identity_config);
 
+        // Sign published messages, and drop any incoming messages without a valid signature
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .protocol_id_prefix(MIRAGE_MESHSUB_ID)
             .heartbeat_interval(Duration::from_secs(10))
-            .validation_mode(gossipsub::ValidationMode::None)
+            .validation_mode(gossipsub::ValidationMode::Strict)
+            // Messages are only forwarded once the node has validated the signal
+            .validate_messages()
             .message_id_fn(|message: &gossipsub::Message| {
                 let mut h = DefaultHasher::new();
                 message.data.hash(&mut h);
@@ -49,11 +66,35 @@ impl MirageBehavior {
             })
             .build()
             .expect("Failed to make the gossipsub conf");
-        let gossipsub = gossipsub::Behaviour::new(
-            gossipsub::MessageAuthenticity::Author(peer_id),
+        let mut gossipsub = gossipsub::Behaviour::new(
+            gossipsub::MessageAuthenticity::Signed(keypair.clone()),
             gossipsub_config,
-        )
-        .unwrap();
+        )?;
+
+        // Score peers by the validity of the signals they deliver
+        let score = &config.score;
+        let signal_params = gossipsub::TopicScoreParams {
+            topic_weight: 1.0,
+            invalid_message_deliveries_weight: score.invalid_message_weight,
+            invalid_message_deliveries_decay: score.invalid_message_decay,
+            // Score peers by what they deliver, not by how long they have been in the mesh
+            time_in_mesh_weight: 0.0,
+            // Signals are infrequent, so don't penalize peers for low delivery rates
+            mesh_message_deliveries_weight: 0.0,
+            mesh_failure_penalty_weight: 0.0,
+            ..Default::default()
+        };
+        let mut score_params = gossipsub::PeerScoreParams::default();
+        score_params
+            .topics
+            .insert(config.signal_topic().hash(), signal_params);
+        let thresholds = gossipsub::PeerScoreThresholds {
+            gossip_threshold: score.gossip_threshold,
+            publish_threshold: score.publish_threshold,
+            graylist_threshold: score.graylist_threshold,
+            ..Default::default()
+        };
+        gossipsub.with_peer_score(score_params, thresholds)?;
 
         let mut kad_config = kad::Config::new(StreamProtocol::new(MIRAGE_DISCOVERY_ID));
         kad_config.set_periodic_bootstrap_interval(Some(config.bootstrap_interval));
@@ -61,14 +102,23 @@ impl MirageBehavior {
         let mut kad = kad::Behaviour::with_config(peer_id, kad_store, kad_config);
         kad.set_mode(Some(kad::Mode::Server));
 
-        Self {
+        // Optionally discover peers on the local network
+        let mdns = config
+            .mdns
+            .then(|| mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id))
+            .transpose()?
+            .into();
+
+        Ok(Self {
+            limits,
             shutdown,
             signal,
             identify,
             ping,
             kad,
             gossipsub,
-        }
+            mdns,
+        })
     }
 }
 
@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+
+/// Upper bound on the delay between redials of an unreachable bootstrap peer
+pub const MAX_BOOTSTRAP_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Tracks connectivity to bootstrap peers, scheduling redials of disconnected
+/// peers with exponential backoff.
+pub struct BootstrapPeers {
+    interval: Duration,
+    peers: Vec<BootstrapPeer>,
+}
+
+struct BootstrapPeer {
+    addr: Multiaddr,
+    /// Peer id, from the address or learned once connected
+    peer_id: Option<PeerId>,
+    connected: bool,
+    /// Delay before the next redial after the current one
+    backoff: Duration,
+    /// Earliest time to redial the peer
+    next_dial: Instant,
+}
+
+impl BootstrapPeers {
+    /// Track the given bootstrap peers, which were initially dialed at `now`
+    pub fn new(addrs: &[Multiaddr], interval: Duration, now: Instant) -> Self {
+        let peers = addrs
+            .iter()
+            .map(|addr| BootstrapPeer {
+                addr: addr.clone(),
+                peer_id: addr.iter().find_map(|p| match p {
+                    Protocol::P2p(peer_id) => Some(peer_id),
+                    _ => None,
+                }),
+                connected: false,
+                backoff: interval,
+                next_dial: now + interval,
+            })
+            .collect();
+        Self { interval, peers }
+    }
+
+    /// Interval between redial checks
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the addresses of disconnected peers due for a redial at the given time,
+    /// doubling their backoff up to [`MAX_BOOTSTRAP_BACKOFF`].
+    pub fn due(&mut self, now: Instant) -> Vec<Multiaddr> {
+        let max = MAX_BOOTSTRAP_BACKOFF.max(self.interval);
+        self.peers
+            .iter_mut()
+            .filter(|peer| !peer.connected && peer.next_dial <= now)
+            .map(|peer| {
+                peer.next_dial = now + peer.backoff;
+                peer.backoff = (peer.backoff * 2).min(max);
+                peer.addr.clone()
+            })
+            .collect()
+    }
+
+    /// Record an established connection, resetting the backoff of a matching bootstrap peer
+    pub fn connected(&mut self, peer_id: PeerId, remote: &Multiaddr) {
+        for peer in &mut self.peers {
+            let matches = match peer.peer_id {
+                Some(id) => id == peer_id,
+                None => without_p2p(&peer.addr) == without_p2p(remote),
+            };
+            if matches {
+                peer.peer_id = Some(peer_id);
+                peer.connected = true;
+                peer.backoff = self.interval;
+            }
+        }
+    }
+
+    /// Record that all connections to a peer were closed, scheduling a redial
+    /// after the interval if it is a bootstrap peer
+    pub fn disconnected(&mut self, peer_id: PeerId, now: Instant) {
+        for peer in &mut self.peers {
+            if peer.peer_id == Some(peer_id) {
+                peer.connected = false;
+                peer.next_dial = now + self.interval;
+            }
+        }
+    }
+}
+
+fn without_p2p(addr: &Multiaddr) -> Multiaddr {
+    addr.iter()
+        .filter(|p| !matches!(p, Protocol::P2p(_)))
+        .collect()
+}
@@ -0,0 +1,37 @@
+//! Versioned wire encoding for gossiped signals.
+//!
+//! Encoded signals are prefixed with a version byte, so nodes on a mixed-version
+//! network reject signals in a schema they don't understand rather than mis-parsing them.
+
+use flexbuffers::{DeserializationError, SerializationError};
+use nomad_types::SignalPayload;
+use thiserror::Error;
+
+/// Current version of the signal encoding
+pub const SIGNAL_ENCODING_VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("Empty signal payload")]
+    Empty,
+    #[error("Unsupported signal encoding version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Failed to decode signal: {0}")]
+    Decode(#[from] DeserializationError),
+}
+
+/// Encode a signal with the current version prefix
+pub fn encode(signal: &SignalPayload) -> Result<Vec<u8>, SerializationError> {
+    let mut encoded = vec![SIGNAL_ENCODING_VERSION];
+    encoded.extend(flexbuffers::to_vec(signal)?);
+    Ok(encoded)
+}
+
+/// Decode a signal, rejecting any encoded with an unsupported version
+pub fn decode(data: &[u8]) -> Result<SignalPayload, DecodeError> {
+    match data.split_first() {
+        None => Err(DecodeError::Empty),
+        Some((&SIGNAL_ENCODING_VERSION, data)) => Ok(flexbuffers::from_slice(data)?),
+        Some((&version, _)) => Err(DecodeError::UnsupportedVersion(version)),
+    }
+}
@@ -1,47 +1,152 @@
 use std::{
     sync::{atomic::AtomicBool, Arc},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use futures::StreamExt;
 use libp2p::{
-    gossipsub::{self, IdentTopic},
-    identify, noise,
-    swarm::SwarmEvent,
-    tcp, yamux, Multiaddr, Swarm,
+    gossipsub::{self, IdentTopic, MessageAcceptance, MessageId, PublishError},
+    identify, mdns, noise,
+    swarm::{dial_opts::DialOpts, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, Swarm,
+};
+use opentelemetry::{
+    global::meter_provider,
+    metrics::{Counter, Gauge, Meter},
+    KeyValue,
 };
 use serde::{Deserialize, Serialize};
-use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle};
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle, time::MissedTickBehavior};
 use tracing::{debug, info, info_span, warn, Instrument};
 
 use nomad_pool::SignalPool;
-use nomad_types::SignalPayload;
+use nomad_types::{primitives::hex, SignalPayload};
 
-use crate::{behaviour::MirageBehaviorEvent, shutdown::Shutdown};
+use crate::{
+    behaviour::MirageBehaviorEvent, bootstrap::BootstrapPeers, codec::DecodeError,
+    limiter::LogRateLimiter, shutdown::Shutdown,
+};
 
 pub mod behaviour;
+pub mod bootstrap;
+pub mod codec;
+pub mod limiter;
 pub mod shutdown;
 #[cfg(test)]
 mod tests;
 
 const MIRAGE_DISCOVERY_ID: &str = "/mirage/discovery";
 const MIRAGE_MESHSUB_ID: &str = "/mirage/meshsub";
+/// Gossipsub topic signals are published on, namespaced by the network id
+const SIGNAL_TOPIC: &str = "mirage-signals";
+/// Gossipsub topic blocks are published on, namespaced by the network id.
+///
+/// Reserved for propagating block headers relevant to escrow watching. Nodes
+/// subscribe so the mesh is in place once it is used, but messages are not
+/// yet processed or forwarded.
+const BLOCK_TOPIC: &str = "eth-blocks";
+/// Minimum time between logged parse failures for a single peer
+const PARSE_FAILURE_LOG_WINDOW: Duration = Duration::from_secs(60);
+/// Maximum number of payload bytes included when logging a parse failure
+const PARSE_FAILURE_LOG_BYTES: usize = 64;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(default)]
 pub struct P2pConfig {
+    /// Network namespace prefixed to gossip topics, so isolated networks
+    /// sharing infrastructure don't receive each other's messages
+    pub network_id: String,
     pub bootstrap: Vec<Multiaddr>,
     #[serde(with = "humantime_serde")]
     pub bootstrap_interval: Duration,
     pub tcp: u16,
+    /// UDP port to additionally listen for QUIC connections on.
+    ///
+    /// Runs alongside the TCP transport, so peers may connect with either.
+    pub quic: Option<u16>,
+    /// Discover and connect to peers on the local network with mDNS
+    pub mdns: bool,
+    /// Gossipsub peer scoring
+    pub score: PeerScoreConfig,
+    /// Connection limits
+    pub limits: ConnectionLimitsConfig,
 }
 
 impl Default for P2pConfig {
     fn default() -> Self {
         Self {
+            network_id: "mainnet".into(),
             bootstrap: Vec::new(),
             bootstrap_interval: Duration::from_secs(5 * 60),
             tcp: 9000,
+            quic: None,
+            mdns: false,
+            score: PeerScoreConfig::default(),
+            limits: ConnectionLimitsConfig::default(),
+        }
+    }
+}
+
+impl P2pConfig {
+    /// Signal topic for the configured network
+    pub fn signal_topic(&self) -> IdentTopic {
+        IdentTopic::new(format!("{}/{SIGNAL_TOPIC}", self.network_id))
+    }
+
+    /// Block topic for the configured network
+    pub fn block_topic(&self) -> IdentTopic {
+        IdentTopic::new(format!("{}/{BLOCK_TOPIC}", self.network_id))
+    }
+}
+
+/// Limits on the number of connections, past which new connections are denied.
+/// Unset limits are unbounded.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ConnectionLimitsConfig {
+    /// Maximum number of established connections from remote peers
+    pub max_established_incoming: Option<u32>,
+    /// Maximum number of established connections to remote peers
+    pub max_established_outgoing: Option<u32>,
+    /// Maximum number of connections in each direction still being negotiated
+    pub max_pending: Option<u32>,
+}
+
+impl Default for ConnectionLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_established_incoming: Some(128),
+            max_established_outgoing: Some(64),
+            max_pending: Some(32),
+        }
+    }
+}
+
+/// Gossipsub peer scoring thresholds. Peers delivering invalid signals are
+/// penalized, and progressively throttled as their score drops.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct PeerScoreConfig {
+    /// Score below which gossip is no longer exchanged with a peer
+    pub gossip_threshold: f64,
+    /// Score below which signals are no longer published to a peer
+    pub publish_threshold: f64,
+    /// Score below which all messages from a peer are ignored
+    pub graylist_threshold: f64,
+    /// Weight applied to the square of a peer's invalid signal count
+    pub invalid_message_weight: f64,
+    /// Decay applied to a peer's invalid signal count every decay interval (1s)
+    pub invalid_message_decay: f64,
+}
+
+impl Default for PeerScoreConfig {
+    fn default() -> Self {
+        Self {
+            gossip_threshold: -10.0,
+            publish_threshold: -50.0,
+            graylist_threshold: -80.0,
+            invalid_message_weight: -10.0,
+            invalid_message_decay: 0.99,
         }
     }
 }
@@ -52,6 +157,57 @@ pub struct P2pNode {
     read_only: Arc<AtomicBool>,
     signal_pool: SignalPool,
     signal_topic: IdentTopic,
+    block_topic: IdentTopic,
+    parse_failures: LogRateLimiter,
+    bootstrap: BootstrapPeers,
+    score: PeerScoreConfig,
+    metrics: P2pMetrics,
+}
+
+/// OpenTelemetry instruments for gossip activity
+struct P2pMetrics {
+    min_peer_score: Gauge<f64>,
+    peers_below_threshold: Gauge<u64>,
+    connected_peers: Gauge<u64>,
+    signals_received: Counter<u64>,
+    signals_published: Counter<u64>,
+    publish_failures: Counter<u64>,
+    parse_failures: Counter<u64>,
+}
+
+impl P2pMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            min_peer_score: meter
+                .f64_gauge("peer_score_min")
+                .with_description("Lowest gossipsub score of connected peers")
+                .build(),
+            peers_below_threshold: meter
+                .u64_gauge("peers_below_score_threshold")
+                .with_description("Number of connected peers scored below each threshold")
+                .build(),
+            connected_peers: meter
+                .u64_gauge("connected_peers")
+                .with_description("Number of connected peers")
+                .build(),
+            signals_received: meter
+                .u64_counter("signals_received")
+                .with_description("Number of valid signals received from the network")
+                .build(),
+            signals_published: meter
+                .u64_counter("signals_published")
+                .with_description("Number of signals published to the network")
+                .build(),
+            publish_failures: meter
+                .u64_counter("signal_publish_failures")
+                .with_description("Number of failures when publishing signals, by reason")
+                .build(),
+            parse_failures: meter
+                .u64_counter("signal_parse_failures")
+                .with_description("Number of received signals that failed to parse")
+                .build(),
+        }
+    }
 }
 
 impl P2pNode {
@@ -75,6 +231,7 @@ impl P2pNode {
                 noise::Config::new,
                 yamux::Config::default,
             )?
+            .with_quic()
             .with_behaviour(|keypair| behaviour::MirageBehavior::new(keypair, &config))?
             .with_swarm_config(|cfg| {
                 cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX))
@@ -87,35 +244,131 @@ impl P2pNode {
         }
 
         // Subscribe to topics
-        let block_topic = gossipsub::IdentTopic::new("eth-blocks");
-        let signal_topic = gossipsub::IdentTopic::new("mirage-signals");
+        let block_topic = config.block_topic();
+        let signal_topic = config.signal_topic();
         swarm.behaviour_mut().gossipsub.subscribe(&block_topic)?;
         swarm.behaviour_mut().gossipsub.subscribe(&signal_topic)?;
 
         // Bind to p2p port
         swarm.listen_on(format!("/ip4/0.0.0.0/tcp/{}", config.tcp).parse().unwrap())?;
+        if let Some(port) = config.quic {
+            swarm.listen_on(format!("/ip4/0.0.0.0/udp/{port}/quic-v1").parse().unwrap())?;
+        }
 
         // Connect to bootstrap nodes
         for peer in &config.bootstrap {
             info!("Connecting to bootstrap peer: {peer}");
             swarm.dial(peer.clone())?;
         }
+        let bootstrap =
+            BootstrapPeers::new(&config.bootstrap, config.bootstrap_interval, Instant::now());
 
         Ok(Self {
             swarm,
             read_only,
             signal_pool,
             signal_topic,
+            block_topic,
+            parse_failures: LogRateLimiter::new(PARSE_FAILURE_LOG_WINDOW),
+            bootstrap,
+            score: config.score.clone(),
+            metrics: P2pMetrics::new(&meter_provider().meter("nomad")),
         })
     }
 
+    /// Report the validation result of a gossiped message, which forwards accepted
+    /// messages and penalizes the peer for rejected ones
+    fn report_message(&mut self, id: &MessageId, peer: &PeerId, acceptance: MessageAcceptance) {
+        let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+        gossipsub.report_message_validation_result(id, peer, acceptance);
+        self.record_peer_scores();
+    }
+
+    /// Record aggregate scores of connected peers. Peers are attacker controlled, so
+    /// they are not recorded individually.
+    fn record_peer_scores(&self) {
+        let gossipsub = &self.swarm.behaviour().gossipsub;
+        let scores = gossipsub
+            .all_peers()
+            .filter_map(|(peer, _)| gossipsub.peer_score(peer))
+            .collect::<Vec<_>>();
+        if let Some(min) = scores.iter().copied().reduce(f64::min) {
+            self.metrics.min_peer_score.record(min, &[]);
+        }
+        for (threshold, value) in [
+            ("gossip", self.score.gossip_threshold),
+            ("publish", self.score.publish_threshold),
+            ("graylist", self.score.graylist_threshold),
+        ] {
+            let below = scores.iter().filter(|score| **score < value).count();
+            self.metrics
+                .peers_below_threshold
+                .record(below as u64, &[KeyValue::new("threshold", threshold)]);
+        }
+    }
+
+    /// Insert a locally ingested signal into our own pool and publish it to the network.
+    ///
+    /// Signals already in the pool have been gossiped before, so are not published
+    /// again and `None` is returned.
+    async fn publish_signal(
+        &mut self,
+        signal: SignalPayload,
+    ) -> Option<Result<MessageId, PublishError>> {
+        let encoded = codec::encode(&signal).unwrap();
+
+        // Read-only nodes don't process signals, so always publish
+        if !self.read_only.load(std::sync::atomic::Ordering::Relaxed)
+            && !self.signal_pool.insert(signal).await
+        {
+            debug!("Not publishing duplicate signal");
+            return None;
+        }
+
+        let res = self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(self.signal_topic.clone(), encoded);
+        match &res {
+            Ok(_) => self.metrics.signals_published.add(1, &[]),
+            Err(e) => {
+                warn!(%e, "Failed to publish outgoing signal");
+                self.metrics
+                    .publish_failures
+                    .add(1, &[KeyValue::new("reason", publish_failure_reason(e))]);
+            }
+        }
+        Some(res)
+    }
+
     pub fn shutdown_handle(&self) -> Shutdown {
         self.swarm.behaviour().shutdown.clone()
     }
 
     pub fn spawn(mut self) -> JoinHandle<eyre::Result<()>> {
         tokio::spawn(async move {
-            while let Some(event) = self.swarm.next().await {
+            let mut redial = tokio::time::interval(self.bootstrap.interval());
+            redial.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                let event = tokio::select! {
+                    event = self.swarm.next() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+
+                    // Redial disconnected bootstrap peers, backing off while they are unreachable
+                    _ = redial.tick() => {
+                        for addr in self.bootstrap.due(Instant::now()) {
+                            debug!(%addr, "Redialing bootstrap peer");
+                            if let Err(e) = self.swarm.dial(addr.clone()) {
+                                warn!(%addr, %e, "Failed to redial bootstrap peer");
+                            }
+                        }
+                        continue;
+                    }
+                };
+
                 match event {
                     // We have a new address
                     SwarmEvent::NewListenAddr { address, .. } => {
@@ -130,23 +383,7 @@ impl P2pNode {
 
                     // Incoming signals
                     SwarmEvent::Behaviour(MirageBehaviorEvent::Signal(signal)) => {
-                        // Encode data
-                        let encoded = flexbuffers::to_vec(&signal).unwrap();
-
-                        // Insert signal into our own signal pool
-                        if !self.read_only.load(std::sync::atomic::Ordering::Relaxed) {
-                            self.signal_pool.insert(signal).await;
-                        }
-
-                        // Publish signal to the network
-                        if let Err(e) = self
-                            .swarm
-                            .behaviour_mut()
-                            .gossipsub
-                            .publish(self.signal_topic.clone(), encoded)
-                        {
-                            warn!(%e, "Failed to publish outgoing signal");
-                        }
+                        self.publish_signal(signal).await;
                     }
 
                     // Peer identified its protocols, connect them to the associated behaviours
@@ -164,31 +401,92 @@ impl P2pNode {
                         }
                     }
 
+                    // Peers discovered on the local network, connect to them
+                    SwarmEvent::Behaviour(MirageBehaviorEvent::Mdns(mdns::Event::Discovered(peers))) => {
+                        for (peer_id, addr) in peers {
+                            debug!(?peer_id, %addr, "Discovered local peer");
+                            self.swarm.behaviour_mut().kad.add_address(&peer_id, addr.clone());
+                            // Only dial peers we aren't already connected to
+                            let opts = DialOpts::peer_id(peer_id).addresses(vec![addr]).build();
+                            if let Err(e) = self.swarm.dial(opts) {
+                                warn!(?peer_id, %e, "Failed to dial local peer");
+                            }
+                        }
+                    }
+
+                    // Peer connected
+                    SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                        self.bootstrap.connected(peer_id, endpoint.get_remote_address());
+                        self.metrics.connected_peers.record(self.swarm.connected_peers().count() as u64, &[]);
+                    }
+
                     // Peer disconnected
                     SwarmEvent::ConnectionClosed { peer_id, num_established, cause, .. } => {
                         debug!(?peer_id, "Connection closed ({num_established} remaining): {cause:?}");
+                        if num_established == 0 {
+                            self.bootstrap.disconnected(peer_id, Instant::now());
+                        }
+                        self.metrics.connected_peers.record(self.swarm.connected_peers().count() as u64, &[]);
+                        self.record_peer_scores();
                     }
 
-                    // Process incoming gossip signals, but only if we are not in read-only mode
+                    // Validate incoming gossip signals
                     SwarmEvent::Behaviour(MirageBehaviorEvent::Gossipsub(
                         gossipsub::Event::Message {
                             message,
+                            message_id,
                             propagation_source,
-                            ..
                         },
-                    )) if !self.read_only.load(std::sync::atomic::Ordering::Relaxed) => {
+                    )) => {
+                        // Block propagation is not implemented yet, so don't forward them
+                        if message.topic == self.block_topic.hash() {
+                            debug!(peer = ?propagation_source, "Ignoring block message");
+                            self.report_message(&message_id, &propagation_source, MessageAcceptance::Ignore);
+                            continue;
+                        }
+
                         if message.topic != self.signal_topic.hash() {
                             warn!(
                                 peer = ?propagation_source,
                                 "Received unrecognized message"
                             );
+                            self.report_message(&message_id, &propagation_source, MessageAcceptance::Ignore);
                             continue;
                         }
 
-                        let Ok(signal) = flexbuffers::from_slice(&message.data) else {
-                            warn!(signal_data = ?String::from_utf8_lossy(&message.data), "Failed to parse received signal");
-                            continue;
+                        let signal = match codec::decode(&message.data) {
+                            Ok(signal) => signal,
+                            Err(e) => {
+                                self.metrics.parse_failures.add(1, &[]);
+                                // Payloads are peer controlled, so rate limit and truncate what we log
+                                if let Some(suppressed) = self.parse_failures.check(propagation_source, Instant::now()) {
+                                    let len = message.data.len().min(PARSE_FAILURE_LOG_BYTES);
+                                    warn!(
+                                        peer = ?propagation_source,
+                                        signal_data = hex::encode(&message.data[..len]),
+                                        signal_len = message.data.len(),
+                                        suppressed,
+                                        %e,
+                                        "Failed to parse received signal"
+                                    );
+                                }
+                                // Penalize the peer for sending garbage, but not for signals from
+                                // newer nodes that we can't decode yet
+                                let acceptance = match e {
+                                    DecodeError::UnsupportedVersion(_) => MessageAcceptance::Ignore,
+                                    _ => MessageAcceptance::Reject,
+                                };
+                                self.report_message(&message_id, &propagation_source, acceptance);
+                                continue;
+                            }
                         };
+                        self.metrics.signals_received.add(1, &[]);
+
+                        // Forward without processing if we are in read-only mode
+                        if self.read_only.load(std::sync::atomic::Ordering::Relaxed) {
+                            self.report_message(&message_id, &propagation_source, MessageAcceptance::Accept);
+                            continue;
+                        }
 
                         // Insert signal to the pool
                         let duplicate = !self.signal_pool.insert(signal).await;
@@ -197,6 +495,14 @@ impl P2pNode {
                             peer = ?propagation_source,
                             "Received signal"
                         );
+
+                        // Don't forward signals we have already seen
+                        let acceptance = if duplicate {
+                            MessageAcceptance::Ignore
+                        } else {
+                            MessageAcceptance::Accept
+                        };
+                        self.report_message(&message_id, &propagation_source, acceptance);
                     }
 
                     _ => {}
@@ -206,3 +512,16 @@ impl P2pNode {
         }.instrument(info_span!("p2p")))
     }
 }
+
+/// Short reason code for a publish failure, used for metrics. Some errors carry
+/// data, so their messages aren't suitable as metric labels.
+fn publish_failure_reason(e: &PublishError) -> &'static str {
+    match e {
+        PublishError::Duplicate => "duplicate",
+        PublishError::SigningError(_) => "signing_failed",
+        PublishError::NoPeersSubscribedToTopic => "no_peers",
+        PublishError::MessageTooLarge => "message_too_large",
+        PublishError::TransformFailed(_) => "transform_failed",
+        PublishError::AllQueuesFull(_) => "queues_full",
+    }
+}
@@ -0,0 +1,44 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use libp2p::PeerId;
+
+/// Per-peer rate limiter for log messages, allowing at most one message
+/// per peer in each window and counting any that were suppressed.
+pub struct LogRateLimiter {
+    window: Duration,
+    peers: HashMap<PeerId, (Instant, usize)>,
+}
+
+impl LogRateLimiter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Check if a message for the peer should be logged at the given time.
+    /// Returns the number of messages suppressed since the last one, if allowed.
+    pub fn check(&mut self, peer: PeerId, now: Instant) -> Option<usize> {
+        if let Some((last, suppressed)) = self.peers.get_mut(&peer) {
+            if now.duration_since(*last) < self.window {
+                *suppressed += 1;
+                return None;
+            }
+            let count = *suppressed;
+            *last = now;
+            *suppressed = 0;
+            return Some(count);
+        }
+
+        // Drop peers outside of the window before tracking a new one
+        let window = self.window;
+        self.peers
+            .retain(|_, (last, _)| now.duration_since(*last) < window);
+        self.peers.insert(peer, (now, 0));
+        Some(0)
+    }
+}
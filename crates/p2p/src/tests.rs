@@ -1,15 +1,33 @@
 use std::{
     iter::repeat_with,
     sync::{atomic::AtomicBool, Arc},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use futures::{future::join_all, StreamExt};
+use libp2p::{gossipsub, noise, ping, swarm::SwarmEvent, tcp, yamux, Multiaddr, PeerId};
+
 use nomad_pool::SignalPool;
 use nomad_types::{primitives::U256, SignalPayload};
 use tokio::sync::mpsc::unbounded_channel;
 use tracing::{info, Level};
 
-use crate::{P2pConfig, P2pNode};
+use crate::{
+    behaviour::MirageBehaviorEvent, bootstrap::BootstrapPeers, codec, limiter::LogRateLimiter,
+    publish_failure_reason, ConnectionLimitsConfig, P2pConfig, P2pNode, MIRAGE_MESHSUB_ID,
+};
+
+fn signal(id: u8) -> SignalPayload {
+    SignalPayload::Unencrypted(nomad_types::Signal {
+        escrow_contract: [id; 20].into(),
+        token_contract: [id; 20].into(),
+        recipient: [id; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    })
+}
 
 macro_rules! port {
     () => {
@@ -94,15 +112,7 @@ async fn bootstrap_and_propagate_signal() -> eyre::Result<()> {
 
     // Test sending a signal to each node
     for i in 0..=2 {
-        let signal = SignalPayload::Unencrypted(nomad_types::Signal {
-            escrow_contract: [i; 20].into(),
-            token_contract: [i; 20].into(),
-            recipient: [i; 20].into(),
-            transfer_amount: U256::from(12345678),
-            reward_amount: U256::from(1234),
-            acknowledgement_url: "https://my-url.com".parse().unwrap(),
-            selector_mapping: Default::default(),
-        });
+        let signal = signal(i);
 
         // Send signal to p2p node to broadcast and index
         txs[i as usize].send(signal.clone()).unwrap();
@@ -123,3 +133,523 @@ async fn bootstrap_and_propagate_signal() -> eyre::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn parse_failure_logs_are_rate_limited() {
+    let window = Duration::from_secs(60);
+    let mut limiter = LogRateLimiter::new(window);
+    let peer = PeerId::random();
+    let other = PeerId::random();
+    let start = Instant::now();
+
+    // Only the first of many messages in a window is logged
+    let logged = (0..100)
+        .filter(|i| {
+            limiter
+                .check(peer, start + Duration::from_millis(*i))
+                .is_some()
+        })
+        .count();
+    assert_eq!(logged, 1);
+
+    // Other peers are limited independently
+    assert_eq!(limiter.check(other, start), Some(0));
+
+    // Next window logs again, reporting the suppressed count
+    assert_eq!(limiter.check(peer, start + window), Some(99));
+}
+
+#[tokio::test]
+async fn unsigned_messages_are_dropped() -> eyre::Result<()> {
+    let signal_pool = SignalPool::new(100);
+    let config = P2pConfig {
+        tcp: port!(),
+        ..Default::default()
+    };
+    let address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", config.tcp).parse()?;
+    let node = P2pNode::new(
+        config,
+        signal_pool.clone(),
+        AtomicBool::new(false).into(),
+        None,
+    )?;
+    let topic = node.signal_topic.clone();
+    let shutdown = node.shutdown_handle();
+    node.spawn();
+
+    // Peer publishing anonymous, unsigned messages
+    let gossipsub_config = gossipsub::ConfigBuilder::default()
+        .protocol_id_prefix(MIRAGE_MESHSUB_ID)
+        .validation_mode(gossipsub::ValidationMode::None)
+        .build()?;
+    let mut peer = libp2p::SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::new(),
+            noise::Config::new,
+            yamux::Config::default,
+        )?
+        .with_behaviour(|_| {
+            gossipsub::Behaviour::<
+                gossipsub::IdentityTransform,
+                gossipsub::AllowAllSubscriptionFilter,
+            >::new(gossipsub::MessageAuthenticity::Anonymous, gossipsub_config)
+            .unwrap()
+        })?
+        .build();
+    peer.behaviour_mut().subscribe(&topic)?;
+    peer.dial(address)?;
+
+    // Wait for the node to subscribe, then publish
+    loop {
+        if let SwarmEvent::Behaviour(gossipsub::Event::Subscribed { topic: t, .. }) =
+            peer.select_next_some().await
+        {
+            if t == topic.hash() {
+                break;
+            }
+        }
+    }
+    let signal = signal(1);
+    peer.behaviour_mut()
+        .publish(topic, codec::encode(&signal)?)?;
+
+    // Keep the peer connection alive while waiting for the signal, which should never arrive
+    let received = tokio::select! {
+        _ = async { loop { peer.select_next_some().await; } } => unreachable!(),
+        res = tokio::time::timeout(Duration::from_secs(2), signal_pool.sample()) => res.is_ok(),
+    };
+    assert!(!received);
+
+    shutdown.shutdown();
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_signals_lower_peer_score() -> eyre::Result<()> {
+    let signal_pool = SignalPool::new(100);
+    let config = P2pConfig {
+        tcp: port!(),
+        ..Default::default()
+    };
+    let address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", config.tcp).parse()?;
+    let node = P2pNode::new(
+        config.clone(),
+        signal_pool.clone(),
+        AtomicBool::new(false).into(),
+        None,
+    )?;
+    let topic = node.signal_topic.clone();
+    let shutdown = node.shutdown_handle();
+    node.spawn();
+
+    // Peer publishing signed, but unparsable signals with a supported version
+    let mut peer = libp2p::SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::new(),
+            noise::Config::new,
+            yamux::Config::default,
+        )?
+        .with_behaviour(|keypair| crate::behaviour::MirageBehavior::new(keypair, &config))?
+        .build();
+    peer.behaviour_mut().gossipsub.subscribe(&topic)?;
+    peer.dial(address)?;
+
+    // Wait for the node to subscribe
+    loop {
+        if let SwarmEvent::Behaviour(MirageBehaviorEvent::Gossipsub(
+            gossipsub::Event::Subscribed { topic: t, .. },
+        )) = peer.select_next_some().await
+        {
+            if t == topic.hash() {
+                break;
+            }
+        }
+    }
+
+    // Enough invalid signals to push the peer's score below the graylist threshold,
+    // which is below the publish threshold
+    let score = &config.score;
+    let count = (score.graylist_threshold / score.invalid_message_weight).sqrt() as u8 + 2;
+    for i in 0..count {
+        peer.behaviour_mut()
+            .gossipsub
+            .publish(topic.clone(), vec![codec::SIGNAL_ENCODING_VERSION, 0xff, i])?;
+    }
+
+    // Give the node time to validate them, while keeping the connection alive
+    let _ = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            peer.select_next_some().await;
+        }
+    })
+    .await;
+
+    // Valid signals from the peer are now ignored by the node
+    let signal = signal(1);
+    peer.behaviour_mut()
+        .gossipsub
+        .publish(topic, codec::encode(&signal)?)?;
+
+    let received = tokio::select! {
+        _ = async { loop { peer.select_next_some().await; } } => unreachable!(),
+        res = tokio::time::timeout(Duration::from_secs(2), signal_pool.sample()) => res.is_ok(),
+    };
+    assert!(!received);
+
+    shutdown.shutdown();
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mdns_discovers_local_peers() -> eyre::Result<()> {
+    let signal_pools = repeat_with(|| SignalPool::new(100))
+        .take(2)
+        .collect::<Vec<_>>();
+    let (tx, rx) = unbounded_channel();
+    let read_only = Arc::new(AtomicBool::new(false));
+
+    // Two nodes without any bootstrap peers
+    let mut shutdowns = Vec::new();
+    for (i, rx) in [Some(rx), None].into_iter().enumerate() {
+        let config = P2pConfig {
+            tcp: port!() + i as u16,
+            mdns: true,
+            ..Default::default()
+        };
+        let node = P2pNode::new(config, signal_pools[i].clone(), read_only.clone(), rx)?;
+        shutdowns.push(node.shutdown_handle());
+        node.spawn();
+    }
+
+    // Wait for them to find each other
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let signal = signal(1);
+    tx.send(signal.clone())?;
+    let received = tokio::time::timeout(Duration::from_secs(5), signal_pools[1].sample()).await?;
+    assert_eq!(signal, received);
+
+    for shutdown in shutdowns {
+        shutdown.shutdown();
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn quic_bootstrap() -> eyre::Result<()> {
+    let signal_pools = repeat_with(|| SignalPool::new(100))
+        .take(2)
+        .collect::<Vec<_>>();
+    let (tx, rx) = unbounded_channel();
+    let read_only = Arc::new(AtomicBool::new(false));
+
+    // Bootstrap node listening on both transports
+    let quic = port!();
+    let node0 = P2pNode::new(
+        P2pConfig {
+            tcp: port!(),
+            quic: Some(quic),
+            ..Default::default()
+        },
+        signal_pools[0].clone(),
+        read_only.clone(),
+        Some(rx),
+    )?;
+    let mut shutdowns = vec![node0.shutdown_handle()];
+    node0.spawn();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Second node bootstraps over QUIC
+    let node1 = P2pNode::new(
+        P2pConfig {
+            tcp: port!(),
+            bootstrap: vec![format!("/ip4/127.0.0.1/udp/{quic}/quic-v1").parse()?],
+            ..Default::default()
+        },
+        signal_pools[1].clone(),
+        read_only,
+        None,
+    )?;
+    shutdowns.push(node1.shutdown_handle());
+    node1.spawn();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let signal = signal(1);
+    tx.send(signal.clone())?;
+    let received = tokio::time::timeout(Duration::from_secs(5), signal_pools[1].sample()).await?;
+    assert_eq!(signal, received);
+
+    for shutdown in shutdowns {
+        shutdown.shutdown();
+    }
+    Ok(())
+}
+
+#[test]
+fn bootstrap_redial_backoff() {
+    let interval = Duration::from_secs(10);
+    let addr: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+    let start = Instant::now();
+    let secs = |s| start + Duration::from_secs(s);
+    let mut peers = BootstrapPeers::new(std::slice::from_ref(&addr), interval, start);
+
+    // Nothing is due until the interval has passed since the initial dial
+    assert!(peers.due(secs(5)).is_empty());
+    assert_eq!(peers.due(secs(10)), vec![addr.clone()]);
+
+    // Backoff doubles while the peer stays unreachable
+    assert!(peers.due(secs(19)).is_empty());
+    assert_eq!(peers.due(secs(20)), vec![addr.clone()]);
+    assert!(peers.due(secs(39)).is_empty());
+    assert_eq!(peers.due(secs(40)), vec![addr.clone()]);
+
+    // Connected peers are never redialed
+    let peer_id = PeerId::random();
+    peers.connected(peer_id, &addr);
+    assert!(peers.due(secs(1000)).is_empty());
+
+    // and are redialed after the interval once disconnected, with the backoff reset
+    peers.disconnected(peer_id, secs(1000));
+    assert!(peers.due(secs(1005)).is_empty());
+    assert_eq!(peers.due(secs(1010)), vec![addr.clone()]);
+    assert_eq!(peers.due(secs(1020)), vec![addr]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bootstrap_peer_comes_online_later() -> eyre::Result<()> {
+    let signal_pools = repeat_with(|| SignalPool::new(100))
+        .take(2)
+        .collect::<Vec<_>>();
+    let (tx, rx) = unbounded_channel();
+    let read_only = Arc::new(AtomicBool::new(false));
+    let bootstrap_port = port!();
+
+    // Start a node while its bootstrap peer is still offline
+    let node = P2pNode::new(
+        P2pConfig {
+            tcp: port!(),
+            bootstrap: vec![format!("/ip4/127.0.0.1/tcp/{bootstrap_port}").parse()?],
+            bootstrap_interval: Duration::from_secs(1),
+            ..Default::default()
+        },
+        signal_pools[1].clone(),
+        read_only.clone(),
+        None,
+    )?;
+    let mut shutdowns = vec![node.shutdown_handle()];
+    node.spawn();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    // Bring the bootstrap peer online
+    let bootstrap = P2pNode::new(
+        P2pConfig {
+            tcp: bootstrap_port,
+            ..Default::default()
+        },
+        signal_pools[0].clone(),
+        read_only,
+        Some(rx),
+    )?;
+    shutdowns.push(bootstrap.shutdown_handle());
+    bootstrap.spawn();
+
+    // Wait for the next redial, and for the mesh to form
+    tokio::time::sleep(Duration::from_secs(4)).await;
+
+    let signal = signal(1);
+    tx.send(signal.clone())?;
+    let received = tokio::time::timeout(Duration::from_secs(5), signal_pools[1].sample()).await?;
+    assert_eq!(signal, received);
+
+    for shutdown in shutdowns {
+        shutdown.shutdown();
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn incoming_connections_are_limited() -> eyre::Result<()> {
+    let config = P2pConfig {
+        tcp: port!(),
+        limits: ConnectionLimitsConfig {
+            max_established_incoming: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", config.tcp).parse()?;
+    let node = P2pNode::new(
+        config,
+        SignalPool::new(100),
+        AtomicBool::new(false).into(),
+        None,
+    )?;
+    let shutdown = node.shutdown_handle();
+    node.spawn();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Connect one more peer than the limit allows, one at a time
+    let mut peers = Vec::new();
+    for _ in 0..3 {
+        let mut peer = libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::new(),
+                noise::Config::new,
+                yamux::Config::default,
+            )?
+            .with_behaviour(|_| ping::Behaviour::default())?
+            .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+        peer.dial(address.clone())?;
+        loop {
+            match peer.select_next_some().await {
+                SwarmEvent::ConnectionEstablished { .. }
+                | SwarmEvent::OutgoingConnectionError { .. } => break,
+                _ => {}
+            }
+        }
+        peers.push(peer);
+    }
+
+    // Drive the peers for a while, so the denied connection is closed
+    let connected = join_all(peers.into_iter().map(|mut peer| async move {
+        let _ = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                peer.select_next_some().await;
+            }
+        })
+        .await;
+        peer.network_info().num_peers()
+    }))
+    .await;
+    assert_eq!(connected, vec![1, 1, 0]);
+
+    shutdown.shutdown();
+    Ok(())
+}
+
+#[tokio::test]
+async fn duplicate_signals_are_not_published() -> eyre::Result<()> {
+    let signal_pool = SignalPool::new(100);
+    let config = P2pConfig {
+        tcp: port!(),
+        ..Default::default()
+    };
+    let mut node = P2pNode::new(
+        config,
+        signal_pool.clone(),
+        AtomicBool::new(false).into(),
+        None,
+    )?;
+
+    let signal = signal(1);
+
+    // A new signal is published (failing here, as there are no peers)
+    assert!(node.publish_signal(signal.clone()).await.is_some());
+    // but a duplicate is not
+    assert!(node.publish_signal(signal).await.is_none());
+    assert_eq!(signal_pool.len(), 1);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn networks_are_isolated() -> eyre::Result<()> {
+    let signal_pools = repeat_with(|| SignalPool::new(100))
+        .take(3)
+        .collect::<Vec<_>>();
+    let (tx, rx) = unbounded_channel();
+    let read_only = Arc::new(AtomicBool::new(false));
+
+    // Bootstrap node on the default network
+    let bootstrap_port = port!();
+    let node = P2pNode::new(
+        P2pConfig {
+            tcp: bootstrap_port,
+            ..Default::default()
+        },
+        signal_pools[0].clone(),
+        read_only.clone(),
+        Some(rx),
+    )?;
+    assert_eq!(node.signal_topic.to_string(), "mainnet/mirage-signals");
+    let mut shutdowns = vec![node.shutdown_handle()];
+    node.spawn();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Peers on the same and on a different network
+    for (i, network_id) in [(1, "mainnet"), (2, "staging")] {
+        let node = P2pNode::new(
+            P2pConfig {
+                network_id: network_id.into(),
+                tcp: port!() + i as u16,
+                bootstrap: vec![format!("/ip4/127.0.0.1/tcp/{bootstrap_port}").parse()?],
+                ..Default::default()
+            },
+            signal_pools[i].clone(),
+            read_only.clone(),
+            None,
+        )?;
+        shutdowns.push(node.shutdown_handle());
+        node.spawn();
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let signal = signal(1);
+    tx.send(signal.clone())?;
+
+    // Only the peer on the same network receives the signal
+    let received = tokio::time::timeout(Duration::from_secs(5), signal_pools[1].sample()).await?;
+    assert_eq!(signal, received);
+    let received = tokio::time::timeout(Duration::from_secs(2), signal_pools[2].sample()).await;
+    assert!(received.is_err());
+
+    for shutdown in shutdowns {
+        shutdown.shutdown();
+    }
+    Ok(())
+}
+
+#[test]
+fn signal_codec_round_trip() -> eyre::Result<()> {
+    let signal = signal(1);
+    let encoded = codec::encode(&signal)?;
+    assert_eq!(encoded[0], codec::SIGNAL_ENCODING_VERSION);
+    assert_eq!(codec::decode(&encoded)?, signal);
+    Ok(())
+}
+
+#[test]
+fn signal_codec_rejects_unknown_versions() -> eyre::Result<()> {
+    let signal = signal(1);
+    let mut encoded = codec::encode(&signal)?;
+    encoded[0] = codec::SIGNAL_ENCODING_VERSION + 1;
+    assert!(matches!(
+        codec::decode(&encoded),
+        Err(codec::DecodeError::UnsupportedVersion(2))
+    ));
+
+    // Unversioned payloads are rejected too
+    assert!(codec::decode(&flexbuffers::to_vec(&signal)?).is_err());
+    assert!(matches!(codec::decode(&[]), Err(codec::DecodeError::Empty)));
+    Ok(())
+}
+
+#[test]
+fn publish_failure_reasons_are_bounded() {
+    assert_eq!(
+        publish_failure_reason(&gossipsub::PublishError::AllQueuesFull(3)),
+        "queues_full"
+    );
+    assert_eq!(
+        publish_failure_reason(&gossipsub::PublishError::AllQueuesFull(7)),
+        "queues_full"
+    );
+    let err = std::io::Error::other("compression failed");
+    assert_eq!(
+        publish_failure_reason(&gossipsub::PublishError::TransformFailed(err)),
+        "transform_failed"
+    );
+}
@@ -1,69 +1,440 @@
 use std::{
-    hash::{Hash, Hasher},
-    sync::Arc,
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, Weak,
+    },
+    time::{Duration, Instant},
 };
 
+use opentelemetry::metrics::{Counter, Meter, ObservableGauge};
+use rand::Rng;
 use scc::{Bag, HashCache};
 use tokio::sync::Notify;
 
-use nomad_types::SignalPayload;
+use nomad_types::{
+    primitives::{B256, U256},
+    SignalPayload,
+};
+
+#[cfg(test)]
+mod tests;
 
-/// Concurrent, lock-free, and unordered signal pool.
+/// Concurrent and unordered signal pool.
 ///
 /// Shared between the gossip layer and the main worker thread, signals are
-/// inserted and then randomly processed by the node.
+/// inserted and then randomly processed by the node. Encrypted signals are held
+/// in a lock-free bag, while signals with a visible reward are indexed by it,
+/// so they can be sampled by reward without scanning the pool.
+///
+/// Pools created with [`SignalPool::new_ordered`] instead process signals
+/// oldest-first. This guarantees older signals are not starved under load, at
+/// the cost of a lock around the underlying queue.
 #[derive(Clone)]
 pub struct SignalPool {
-    /// Cache containing hashes of signals for rejecting duplicates
-    cache: Arc<HashCache<u64, ()>>,
-    /// Concurrent, lock-free, and unordered container.
-    bag: Arc<Bag<SignalPayload>>,
-    /// Notify handle for awaiting first signals
+    /// Cache containing the ids of previously seen signals for rejecting duplicates.
+    ///
+    /// Keyed on the keccak256 [`SignalPayload::id`] rather than the payload, so
+    /// the cache stays small while collisions remain infeasible.
+    cache: Arc<HashCache<B256, ()>>,
+    /// Container holding the signals
+    store: Arc<Store>,
+    /// Notify handle for awaiting new signals
     notify: Arc<Notify>,
-    /// Maximum size bag is allowed to grow to
+    /// Maximum number of signals the pool is allowed to grow to
     max_size: usize,
+    /// Maximum age of signals before they are expired
+    ttl: Option<Duration>,
+    /// Running totals, shared between clones
+    totals: Arc<Totals>,
+    /// Optional metrics, shared between clones once installed
+    metrics: Arc<OnceLock<PoolMetrics>>,
+}
+
+/// Running totals of pool activity
+#[derive(Default)]
+struct Totals {
+    inserted: AtomicU64,
+    duplicates: AtomicU64,
+}
+
+/// OpenTelemetry instruments for pool activity
+struct PoolMetrics {
+    _size: ObservableGauge<u64>,
+    inserts: Counter<u64>,
+    duplicates: Counter<u64>,
+    evictions: Counter<u64>,
+    samples: Counter<u64>,
+}
+
+/// Underlying signal container
+enum Store {
+    /// Unordered container, split by whether the reward is visible
+    Random {
+        /// Concurrent, lock-free, and unordered container for signals without a reward
+        bag: Box<Bag<Entry>>,
+        /// Signals keyed by reward, and an insertion counter to keep keys unique
+        rewards: Mutex<BTreeMap<(U256, u64), Entry>>,
+        /// Next insertion counter for the reward index
+        next: AtomicU64,
+    },
+    /// Insertion ordered queue
+    Ordered(Mutex<VecDeque<Entry>>),
+}
+
+/// Signal stored in the pool, indexed by its reward
+struct Entry {
+    signal: SignalPayload,
+    /// Reward amount, or zero if the signal is encrypted and the reward is not visible
+    reward: U256,
+    /// Time the signal was inserted into the pool
+    inserted: Instant,
+}
+
+impl From<SignalPayload> for Entry {
+    fn from(signal: SignalPayload) -> Self {
+        let reward = match &signal {
+            SignalPayload::Unencrypted(signal) | SignalPayload::TracedUnencrypted(signal, _) => {
+                signal.reward_amount
+            }
+            SignalPayload::Encrypted(_) | SignalPayload::TracedEncrypted(..) => U256::ZERO,
+        };
+        Self {
+            signal,
+            reward,
+            inserted: Instant::now(),
+        }
+    }
 }
 
 impl SignalPool {
     /// Create a new signal pool with a given maximum number of signals to store
     pub fn new(max_size: usize) -> Self {
+        let store = Store::Random {
+            bag: Bag::new().into(),
+            rewards: Mutex::default(),
+            next: AtomicU64::new(0),
+        };
+        Self::with_store(max_size, store)
+    }
+
+    /// Create a new signal pool that samples signals in insertion order.
+    ///
+    /// Once full, the oldest signal is discarded to make room for new ones.
+    pub fn new_ordered(max_size: usize) -> Self {
+        Self::with_store(max_size, Store::Ordered(Mutex::default()))
+    }
+
+    fn with_store(max_size: usize, store: Store) -> Self {
         Self {
             cache: HashCache::with_capacity(0, max_size * 8).into(),
-            bag: Bag::new().into(),
+            store: store.into(),
             notify: Default::default(),
             max_size,
+            ttl: None,
+            totals: Default::default(),
+            metrics: Default::default(),
         }
     }
 
+    /// Install pool metrics on the given meter. Has no effect if already installed.
+    pub fn install_metrics(&self, meter: &Meter) {
+        let store = self.store.clone();
+        let size = meter
+            .u64_observable_gauge("pool_size")
+            .with_description("Number of signals in the pool")
+            .with_callback(move |observer| observer.observe(store.len() as u64, &[]))
+            .build();
+        let inserts = meter
+            .u64_counter("pool_inserts")
+            .with_description("Number of signals inserted into the pool")
+            .build();
+        let duplicates = meter
+            .u64_counter("pool_duplicates")
+            .with_description("Number of duplicate signals rejected by the pool")
+            .build();
+        let evictions = meter
+            .u64_counter("pool_evictions")
+            .with_description("Number of signals discarded after the pool reached its maximum size")
+            .build();
+        let samples = meter
+            .u64_counter("pool_samples")
+            .with_description("Number of signals sampled from the pool")
+            .build();
+
+        let _ = self.metrics.set(PoolMetrics {
+            _size: size,
+            inserts,
+            duplicates,
+            evictions,
+            samples,
+        });
+    }
+
+    /// Create a new signal pool that expires signals older than `ttl`.
+    ///
+    /// Expired signals are never sampled, and a background task periodically
+    /// sweeps them from the pool. Must be called from within a tokio runtime.
+    pub fn new_with_ttl(max_size: usize, ttl: Duration) -> Self {
+        let pool = Self {
+            ttl: Some(ttl),
+            ..Self::new(max_size)
+        };
+
+        // Sweep until the pool is dropped
+        let store = Arc::downgrade(&pool.store);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ttl);
+            loop {
+                interval.tick().await;
+                let Some(store) = Weak::upgrade(&store) else {
+                    break;
+                };
+                store.expire(ttl);
+            }
+        });
+
+        pool
+    }
+
+    /// Remove all signals older than the given age, returning the number removed
+    pub fn expire_older_than(&self, age: Duration) -> usize {
+        self.store.expire(age)
+    }
+
+    /// Number of signals currently in the pool
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Returns true if the pool contains no signals
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of signals the pool will hold
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Total number of signals inserted into the pool
+    pub fn total_inserted(&self) -> u64 {
+        self.totals.inserted.load(Ordering::Relaxed)
+    }
+
+    /// Total number of duplicate signals rejected by the pool
+    pub fn total_duplicates(&self) -> u64 {
+        self.totals.duplicates.load(Ordering::Relaxed)
+    }
+
     /// Insert a signal into the pool, returning true if not duplicated
     pub async fn insert(&self, signal: SignalPayload) -> bool {
-        // Hash signal and insert into cache
-        let hasher = &mut std::hash::DefaultHasher::new();
-        signal.hash(hasher);
-        if self.cache.put_async(hasher.finish(), ()).await.is_err() {
-            return false;
+        let inserted = self.insert_inner(signal).await;
+        if inserted {
+            self.notify.notify_waiters();
         }
+        inserted
+    }
 
-        let notify = self.bag.is_empty();
-        self.bag.push(signal);
-
-        if notify {
+    /// Insert a batch of signals into the pool, returning the number that were not duplicated.
+    ///
+    /// Waiters are only notified once for the whole batch.
+    pub async fn insert_many(&self, signals: impl IntoIterator<Item = SignalPayload>) -> usize {
+        let mut inserted = 0;
+        for signal in signals {
+            if self.insert_inner(signal).await {
+                inserted += 1;
+            }
+        }
+        if inserted > 0 {
             self.notify.notify_waiters();
         }
+        inserted
+    }
+
+    /// Insert a signal without notifying waiters, returning true if not duplicated
+    async fn insert_inner(&self, signal: SignalPayload) -> bool {
+        // Insert signal id into cache, rejecting signals we've already seen
+        if self.cache.put_async(signal.id(), ()).await.is_err() {
+            self.totals.duplicates.fetch_add(1, Ordering::Relaxed);
+            if let Some(metrics) = self.metrics.get() {
+                metrics.duplicates.add(1, &[]);
+            }
+            return false;
+        }
+
+        self.store.push(signal.into());
+        self.totals.inserted.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics.get() {
+            metrics.inserts.add(1, &[]);
+        }
 
-        // Discard random signal
-        if self.bag.len() > self.max_size {
-            self.bag.pop();
+        // Discard a signal once full
+        if self.store.len() > self.max_size {
+            self.store.evict();
+            if let Some(metrics) = self.metrics.get() {
+                metrics.evictions.add(1, &[]);
+            }
         }
 
         true
     }
 
-    /// Sample and remove a random signal from the pool, waiting if no items are available
+    /// Sample and remove a signal from the pool, waiting if no items are available.
+    ///
+    /// Signals are sampled uniformly at random, regardless of reward, or oldest-first
+    /// for ordered pools.
     pub async fn sample(&self) -> SignalPayload {
-        if self.bag.is_empty() {
-            self.notify.notified().await;
+        self.sample_min_reward(U256::ZERO).await
+    }
+
+    /// Sample and remove a signal with a reward of at least `min`, waiting
+    /// until one is available.
+    ///
+    /// Rewards are only visible for unencrypted signals. Encrypted signals are
+    /// treated as having a reward of zero, so they are only sampled when `min` is zero.
+    /// With a non-zero `min`, unordered pools return the highest reward signal, and
+    /// ordered pools the oldest signal meeting the threshold. A zero `min` samples
+    /// like [`Self::sample`].
+    pub async fn sample_min_reward(&self, min: U256) -> SignalPayload {
+        loop {
+            // Register for notifications before checking, so inserts aren't missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(entry) = self.store.take(self.ttl, min) {
+                if let Some(metrics) = self.metrics.get() {
+                    metrics.samples.add(1, &[]);
+                }
+                return entry.signal;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Store {
+    fn push(&self, entry: Entry) {
+        match self {
+            Store::Random { bag, .. } if entry.reward.is_zero() => bag.push(entry),
+            Store::Random { rewards, next, .. } => {
+                let key = (entry.reward, next.fetch_add(1, Ordering::Relaxed));
+                rewards.lock().unwrap().insert(key, entry);
+            }
+            Store::Ordered(queue) => queue.lock().unwrap().push_back(entry),
         }
-        self.bag.pop().unwrap()
     }
+
+    fn len(&self) -> usize {
+        match self {
+            Store::Random { bag, rewards, .. } => bag.len() + rewards.lock().unwrap().len(),
+            Store::Ordered(queue) => queue.lock().unwrap().len(),
+        }
+    }
+
+    /// Discard a random entry, or the oldest entry if ordered.
+    ///
+    /// When the random entry has a visible reward, the lowest reward is discarded.
+    fn evict(&self) {
+        match self {
+            Store::Random { bag, rewards, .. } => {
+                if pick_bag(bag, rewards) {
+                    drop(bag.pop());
+                } else {
+                    drop(rewards.lock().unwrap().pop_first());
+                }
+            }
+            Store::Ordered(queue) => drop(queue.lock().unwrap().pop_front()),
+        }
+    }
+
+    /// Remove an entry with a reward of at least `min`, dropping any expired entries encountered
+    fn take(&self, ttl: Option<Duration>, min: U256) -> Option<Entry> {
+        let expired = |entry: &Entry| ttl.is_some_and(|ttl| entry.inserted.elapsed() > ttl);
+        match self {
+            Store::Random { bag, rewards, .. } if min.is_zero() => {
+                // Uniformly random across both containers
+                loop {
+                    let entry = if pick_bag(bag, rewards) {
+                        bag.pop()
+                    } else {
+                        let mut rewards = rewards.lock().unwrap();
+                        let index = (!rewards.is_empty())
+                            .then(|| rand::rng().random_range(0..rewards.len()));
+                        let key = index.and_then(|i| rewards.keys().nth(i).copied());
+                        key.and_then(|key| rewards.remove(&key))
+                    };
+                    match entry {
+                        Some(entry) if !expired(&entry) => return Some(entry),
+                        // Both containers are empty
+                        None if self.len() == 0 => return None,
+                        _ => {}
+                    }
+                }
+            }
+            Store::Random { rewards, .. } => {
+                // Highest rewards first, since signals in the bag have no visible reward
+                let mut rewards = rewards.lock().unwrap();
+                loop {
+                    match rewards.last_key_value() {
+                        Some(((reward, _), _)) if *reward >= min => {}
+                        _ => return None,
+                    }
+                    let (_, entry) = rewards.pop_last().unwrap();
+                    if !expired(&entry) {
+                        return Some(entry);
+                    }
+                }
+            }
+            Store::Ordered(queue) => {
+                let mut queue = queue.lock().unwrap();
+                queue.retain(|entry| !expired(entry));
+                let index = queue.iter().position(|entry| entry.reward >= min)?;
+                queue.remove(index)
+            }
+        }
+    }
+
+    /// Remove entries older than the given age, returning the number removed
+    fn expire(&self, age: Duration) -> usize {
+        match self {
+            Store::Random { bag, rewards, .. } => {
+                let mut rewards = rewards.lock().unwrap();
+                let len = rewards.len();
+                rewards.retain(|_, entry| entry.inserted.elapsed() <= age);
+                expire(bag, age) + len - rewards.len()
+            }
+            Store::Ordered(queue) => {
+                let mut queue = queue.lock().unwrap();
+                let len = queue.len();
+                queue.retain(|entry| entry.inserted.elapsed() <= age);
+                len - queue.len()
+            }
+        }
+    }
+}
+
+/// Randomly choose between the bag and the reward index, weighted by their sizes
+fn pick_bag(bag: &Bag<Entry>, rewards: &Mutex<BTreeMap<(U256, u64), Entry>>) -> bool {
+    let bag_len = bag.len();
+    let total = bag_len + rewards.lock().unwrap().len();
+    total > 0 && rand::rng().random_range(0..total) < bag_len
+}
+
+/// Drain entries older than the given age from the bag, returning the number removed
+fn expire(bag: &Bag<Entry>, age: Duration) -> usize {
+    let mut fresh = Vec::new();
+    let mut expired = 0;
+    while let Some(entry) = bag.pop() {
+        if entry.inserted.elapsed() > age {
+            expired += 1;
+        } else {
+            fresh.push(entry);
+        }
+    }
+    for entry in fresh {
+        bag.push(entry);
+    }
+    expired
 }
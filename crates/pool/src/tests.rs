@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use nomad_types::{primitives::U256, EncryptedSignal, Signal, SignalPayload};
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry_sdk::metrics::{
+    data::{AggregatedMetrics, MetricData},
+    InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+};
+
+use crate::SignalPool;
+
+fn signal(id: u8, reward: u64) -> SignalPayload {
+    SignalPayload::Unencrypted(Signal {
+        escrow_contract: [id; 20].into(),
+        token_contract: [id; 20].into(),
+        recipient: [id; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(reward),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    })
+}
+
+fn encrypted_signal(id: u8) -> SignalPayload {
+    SignalPayload::Encrypted(EncryptedSignal {
+        token_contract: [id; 20].into(),
+        relay: "https://my-url.com".parse().unwrap(),
+        puzzle: vec![id; 32].into(),
+        data: vec![id; 32].into(),
+    })
+}
+
+#[tokio::test]
+async fn sample_min_reward() {
+    let pool = SignalPool::new(100);
+    assert!(pool.insert(signal(1, 10)).await);
+    assert!(pool.insert(signal(2, 1000)).await);
+    assert!(pool.insert(encrypted_signal(3)).await);
+
+    assert_eq!(
+        pool.sample_min_reward(U256::from(500)).await,
+        signal(2, 1000)
+    );
+
+    // No signals left above the threshold
+    let res = tokio::time::timeout(
+        Duration::from_millis(50),
+        pool.sample_min_reward(U256::from(500)),
+    )
+    .await;
+    assert!(res.is_err());
+
+    // Remaining signals, including encrypted ones, are still available without a threshold
+    let mut rest = vec![pool.sample().await, pool.sample().await];
+    rest.sort_by_key(|s| s.token_contract());
+    assert_eq!(rest, vec![signal(1, 10), encrypted_signal(3)]);
+}
+
+#[tokio::test]
+async fn sample_min_reward_uses_reward_index() {
+    let pool = SignalPool::new(100);
+    for id in 1..=50 {
+        assert!(pool.insert(encrypted_signal(id)).await);
+    }
+    for (id, reward) in [(51, 300), (52, 900), (53, 600)] {
+        assert!(pool.insert(signal(id, reward)).await);
+    }
+
+    // Highest rewards first, leaving encrypted signals in place
+    for (id, reward) in [(52, 900), (53, 600), (51, 300)] {
+        assert_eq!(
+            pool.sample_min_reward(U256::from(100)).await,
+            signal(id, reward)
+        );
+    }
+    assert_eq!(pool.len(), 50);
+}
+
+#[tokio::test]
+async fn sample_ignores_rewards() {
+    let pool = SignalPool::new(200);
+    for id in 1..=100 {
+        assert!(pool.insert(signal(id, id as u64)).await);
+    }
+
+    // Drawing the 10 highest rewards in order is practically impossible when uniform
+    let mut rewards = Vec::new();
+    for _ in 0..10 {
+        let SignalPayload::Unencrypted(signal) = pool.sample().await else {
+            unreachable!()
+        };
+        rewards.push(signal.reward_amount.to::<u64>());
+    }
+    assert_ne!(rewards, (91..=100).rev().collect::<Vec<_>>());
+    assert_eq!(pool.len(), 90);
+}
+
+#[tokio::test]
+async fn sample_waits_for_insert() {
+    let pool = SignalPool::new(100);
+    let sampler = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.sample_min_reward(U256::from(100)).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    pool.insert(signal(1, 10)).await;
+    pool.insert(signal(2, 100)).await;
+    assert_eq!(sampler.await.unwrap(), signal(2, 100));
+}
+
+#[tokio::test]
+async fn expire_older_than() {
+    let pool = SignalPool::new(100);
+    pool.insert(signal(1, 10)).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    pool.insert(signal(2, 10)).await;
+
+    assert_eq!(pool.expire_older_than(Duration::from_millis(10)), 1);
+    assert_eq!(pool.sample().await, signal(2, 10));
+}
+
+#[tokio::test]
+async fn ttl_skips_expired_signals() {
+    let pool = SignalPool::new_with_ttl(100, Duration::from_millis(10));
+    pool.insert(signal(1, 10)).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let res = tokio::time::timeout(Duration::from_millis(50), pool.sample()).await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn install_metrics() {
+    let exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let pool = SignalPool::new(1);
+    pool.install_metrics(&meter_provider.meter("test"));
+
+    // Metrics are shared with clones
+    let clone = pool.clone();
+    assert!(clone.insert(signal(1, 10)).await);
+    assert!(!clone.insert(signal(1, 10)).await);
+    assert!(clone.insert(signal(2, 10)).await);
+
+    meter_provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let value = |name: &str| {
+        let metric = metrics
+            .iter()
+            .flat_map(|rm| rm.scope_metrics())
+            .flat_map(|sm| sm.metrics())
+            .find(|m| m.name() == name)
+            .unwrap_or_else(|| panic!("missing {name}"));
+        match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => {
+                sum.data_points().next().unwrap().value()
+            }
+            AggregatedMetrics::U64(MetricData::Gauge(gauge)) => {
+                gauge.data_points().next().unwrap().value()
+            }
+            _ => panic!("{name} should be a u64 sum or gauge"),
+        }
+    };
+    assert_eq!(value("pool_inserts"), 2);
+    assert_eq!(value("pool_duplicates"), 1);
+    assert_eq!(value("pool_evictions"), 1);
+    assert_eq!(value("pool_size"), 1);
+}
+
+#[tokio::test]
+async fn ordered_sample_is_fifo() {
+    let pool = SignalPool::new_ordered(10);
+    for id in 1..=3 {
+        assert!(pool.insert(signal(id, 10)).await);
+    }
+    for id in 1..=3 {
+        assert_eq!(pool.sample().await, signal(id, 10));
+    }
+}
+
+#[tokio::test]
+async fn similar_signals_are_not_duplicates() {
+    let pool = SignalPool::new(100);
+
+    // Payloads differing by a single field
+    let a = signal(1, 10);
+    let b = signal(1, 11);
+    assert!(pool.insert(a.clone()).await);
+    assert!(pool.insert(b.clone()).await);
+    assert!(!pool.insert(a).await);
+    assert!(!pool.insert(b).await);
+
+    // Encrypted payloads with the same bytes split differently between fields
+    let SignalPayload::Encrypted(mut c) = encrypted_signal(2) else {
+        unreachable!()
+    };
+    let mut d = c.clone();
+    c.puzzle = vec![2; 16].into();
+    c.data = vec![2; 48].into();
+    d.puzzle = vec![2; 48].into();
+    d.data = vec![2; 16].into();
+    assert!(pool.insert(SignalPayload::Encrypted(c)).await);
+    assert!(pool.insert(SignalPayload::Encrypted(d)).await);
+}
+
+#[tokio::test]
+async fn insert_many() {
+    let pool = SignalPool::new_ordered(2);
+    assert!(pool.insert(signal(1, 10)).await);
+
+    // Duplicates are not counted, and the oldest signals are evicted once full
+    let batch = [signal(1, 10), signal(2, 10), signal(3, 10), signal(3, 10)];
+    assert_eq!(pool.insert_many(batch).await, 2);
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.total_inserted(), 3);
+    assert_eq!(pool.total_duplicates(), 2);
+    assert_eq!(pool.sample().await, signal(2, 10));
+    assert_eq!(pool.sample().await, signal(3, 10));
+}
@@ -1,12 +1,18 @@
 use std::hash::{Hash, Hasher};
 
-use alloy_primitives::{Address, Bytes, U256};
+use aes_gcm::{aead::AeadMutInPlace, Aes256Gcm, KeyInit};
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use thiserror::Error;
 use url::Url;
+use zeroize::Zeroizing;
 
 pub use alloy_primitives as primitives;
 
 mod selectors;
+#[cfg(test)]
+mod tests;
 
 pub use hex_schema::*;
 pub use selectors::*;
@@ -30,6 +36,21 @@ impl SignalPayload {
         }
     }
 
+    /// Deterministic signal id, the keccak256 hash of the json encoded signal.
+    ///
+    /// Trace ids are not included, so traced and untraced payloads share the same id.
+    pub fn id(&self) -> B256 {
+        let encoded = match self {
+            SignalPayload::Encrypted(signal) | SignalPayload::TracedEncrypted(signal, _) => {
+                serde_json::to_vec(signal)
+            }
+            SignalPayload::Unencrypted(signal) | SignalPayload::TracedUnencrypted(signal, _) => {
+                serde_json::to_vec(signal)
+            }
+        };
+        keccak256(encoded.expect("signals are always serializable"))
+    }
+
     pub fn trace_id(&self) -> Option<[u8; 16]> {
         match self {
             SignalPayload::TracedEncrypted(_, trace)
@@ -56,6 +77,47 @@ pub struct EncryptedSignal {
     pub data: Bytes,
 }
 
+impl EncryptedSignal {
+    /// Encrypt a signal with the relay (k1) and puzzle output (k2) key shares.
+    ///
+    /// The data field contains the nonce followed by the AES-256-GCM encrypted
+    /// json signal, which is the layout nodes expect when decrypting.
+    pub fn encrypt(
+        signal: &Signal,
+        relay: Url,
+        puzzle: Bytes,
+        k1: &[u8; 32],
+        k2: &[u8; 32],
+        nonce: [u8; 12],
+    ) -> Self {
+        let mut ciphertext = serde_json::to_vec(signal).expect("signals always serialize");
+        Aes256Gcm::new(signal_key(k1, k2).as_ref().into())
+            .encrypt_in_place(&nonce.into(), &[], &mut ciphertext)
+            .expect("encrypting into a vec is infallible");
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+        Self {
+            token_contract: signal.token_contract,
+            relay,
+            puzzle,
+            data: data.into(),
+        }
+    }
+}
+
+/// Derive the signal encryption key from the relay and puzzle key shares,
+/// hashing `sha3_256(k1 . k2)` with the shares sorted so their order doesn't matter.
+pub fn signal_key(k1: &[u8; 32], k2: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    let mut sorted_shares = [*k1, *k2];
+    sorted_shares.sort();
+    let mut key = Zeroizing::new([0u8; 32]);
+    Sha3_256::new()
+        .chain_update(sorted_shares.as_flattened())
+        .finalize_into((&mut *key).into());
+    key
+}
+
 /// Decrypted signal payload containing all information required to execute
 #[derive(Deserialize, Serialize, ToSchema, Clone, PartialEq, Eq)]
 pub struct Signal {
@@ -83,6 +145,55 @@ pub struct Signal {
     pub selector_mapping: Option<SelectorMapping>,
 }
 
+/// Reasons a signal is rejected before execution
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignalError {
+    #[error("Transfer amount must be non-zero")]
+    ZeroTransferAmount,
+    #[error("Reward amount must be non-zero")]
+    ZeroRewardAmount,
+    #[error("Reward amount ({0}) must be less than the transfer amount ({1})")]
+    RewardExceedsTransfer(U256, U256),
+    #[error("{0} must not be the zero address")]
+    ZeroAddress(&'static str),
+    #[error("{0} and {1} must be different addresses")]
+    DuplicateAddress(&'static str, &'static str),
+}
+
+impl Signal {
+    /// Check the signal is sensible to execute, without any network requests
+    pub fn validate(&self) -> Result<(), SignalError> {
+        if self.transfer_amount.is_zero() {
+            return Err(SignalError::ZeroTransferAmount);
+        }
+        if self.reward_amount.is_zero() {
+            return Err(SignalError::ZeroRewardAmount);
+        }
+        if self.reward_amount >= self.transfer_amount {
+            return Err(SignalError::RewardExceedsTransfer(
+                self.reward_amount,
+                self.transfer_amount,
+            ));
+        }
+
+        let addresses = [
+            ("Escrow contract", self.escrow_contract),
+            ("Token contract", self.token_contract),
+            ("Recipient", self.recipient),
+        ];
+        for (i, (name, address)) in addresses.iter().enumerate() {
+            if address.is_zero() {
+                return Err(SignalError::ZeroAddress(name));
+            }
+            if let Some((other, _)) = addresses[i + 1..].iter().find(|(_, a)| a == address) {
+                return Err(SignalError::DuplicateAddress(name, other));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Hash for Signal {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.escrow_contract.hash(state);
@@ -124,6 +235,26 @@ pub struct ReceiptFormat {
     pub bond_transaction_hash: String,
     pub approval_transaction_hash: String,
     pub transfer_transaction_hash: String,
+    /// Missing if collecting the reward failed, after the recipient was already paid
+    pub collect_transaction_hash: Option<String>,
+}
+
+/// Intended actions for executing a signal, from a dry run
+#[derive(Deserialize, Serialize, ToSchema, Clone, Debug, PartialEq, Eq)]
+pub struct DryRunReport {
+    /// Account approving and bonding tokens to the escrow
+    #[schema(value_type = HexAddress)]
+    pub bond_account: Address,
+    /// Account transferring tokens to the recipient
+    #[schema(value_type = HexAddress)]
+    pub transfer_account: Address,
+    /// Amount of tokens to bond
+    #[schema(value_type = U256String)]
+    pub bond_amount: U256,
+    /// Estimated gas for approving the bond
+    pub approve_gas: u64,
+    /// Estimated gas for the transfer
+    pub transfer_gas: u64,
 }
 
 mod hex_schema {
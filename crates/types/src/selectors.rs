@@ -1,5 +1,5 @@
 use crate::HexSelector;
-use alloy_primitives::{Bytes, Selector, U256};
+use alloy_primitives::{Bytes, Selector};
 
 /// Generate escrow contract selectors and mapping struct
 macro_rules! impl_contract_selectors {
@@ -86,88 +86,27 @@ impl EscrowMappings {
 
         Ok(())
     }
-}
-
-pub type SelectorMapping = EscrowMappings;
-
-/// Make raw calls with obfuscated selectors
-///
-/// We manually construct call data for obfuscated contracts because Alloy's
-/// generated bindings can't handle runtime selector changes.
-///
-/// When using standard contracts, Alloy automatically handles call data construction:
-/// ```rust,ignore
-/// // When you write this:
-/// let escrow = Escrow::new(address, &provider);
-/// escrow.bond(U256::from(1000)).send().await?;
-///
-/// Alloy automatically:
-/// 1. Takes the function signature: bond(uint256)
-/// 2. Computes the selector: keccak256("bond(uint256)")[0:4] = 0x9940686e
-/// 3. Encodes parameters: 1000 → [0x00, 0x00, ..., 0x03, 0xe8] (32 bytes)
-/// 4. Combines: [0x99, 0x40, 0x68, 0x6e] + [0x00, ..., 0x03, 0xe8]
-/// 5. Sends the transaction
-/// ```
-///
-/// But for obfuscated contracts, the selectors are different at runtime, so we must
-/// manually construct the call data with the correct obfuscated selectors.
-pub struct ObfuscatedCaller {
-    pub selector_mapping: EscrowMappings,
-}
-
-impl ObfuscatedCaller {
-    pub fn new(selector_mapping: EscrowMappings) -> Self {
-        Self { selector_mapping }
-    }
 
-    /// Check if contract should use obfuscated calls
-    pub fn is_obfuscated(&self) -> bool {
-        self.selector_mapping.bond != Selector::ZERO
-            || self.selector_mapping.collect != Selector::ZERO
-            || self.selector_mapping.is_bonded != Selector::ZERO
+    /// Translate an escrow function selector to its obfuscated selector, returning
+    /// `None` for unknown or unmapped selectors
+    pub fn remap(&self, original: [u8; 4]) -> Option<[u8; 4]> {
+        self.get_obfuscated_selector(original.into())
+            .filter(|selector| *selector != Selector::ZERO)
+            .map(|selector| selector.0)
     }
 
-    /// Prepare is_bonded() call data using obfuscated selector
-    pub fn is_bonded_call_data(&self) -> Result<Bytes, String> {
-        let selector = self.selector_mapping.is_bonded;
-        if selector == Selector::ZERO {
-            return Err("Missing is_bonded selector mapping".to_string());
-        }
-
-        let call_data = selector.to_vec(); // is_bonded() has no parameters
-        Ok(call_data.into())
-    }
-
-    /// Prepare bond(uint256) call data using obfuscated selector
-    pub fn bond_call_data(&self, bond_amount: U256) -> Result<Bytes, String> {
-        let selector = self.selector_mapping.bond;
-        if selector == Selector::ZERO {
-            return Err("Missing bond selector mapping".to_string());
-        }
-
-        let mut call_data = selector.to_vec();
-
-        // Encode uint256 parameter (32 bytes, big-endian)
-        let amount_bytes = bond_amount.to_be_bytes::<32>();
-        call_data.extend_from_slice(&amount_bytes);
-
-        Ok(call_data.into())
-    }
-
-    /// Prepare collect() call data using obfuscated selector
-    pub fn collect_call_data(&self) -> Result<Bytes, String> {
-        let selector = self.selector_mapping.collect;
-        if selector == Selector::ZERO {
-            return Err("Missing collect selector mapping".to_string());
-        }
-
-        let call_data = selector.to_vec(); // collect() has no parameters
-        Ok(call_data.into())
-    }
-
-    /// Parse boolean result from is_bonded call
-    pub fn parse_bool_result(&self, result: &[u8]) -> bool {
-        // Boolean results are returned as 32 bytes with the last byte containing the bool value
-        result.len() >= 32 && result[31] != 0
+    /// Rewrite the leading function selector of encoded call data with [`Self::remap`],
+    /// returning `None` and leaving the data unchanged if it can't be translated.
+    ///
+    /// Generated contract bindings always encode the original selectors, so calls to
+    /// obfuscated contracts are encoded as usual and then translated.
+    pub fn apply_to_calldata(&self, data: &mut Bytes) -> Option<()> {
+        let remapped = self.remap(data.get(..4)?.try_into().unwrap())?;
+        let mut buf = data.to_vec();
+        buf[..4].copy_from_slice(&remapped);
+        *data = buf.into();
+        Some(())
     }
 }
+
+pub type SelectorMapping = EscrowMappings;
@@ -0,0 +1,167 @@
+use alloy_primitives::{fixed_bytes, Address, Bytes, U256};
+
+use crate::{
+    signal_key, EncryptedSignal, SelectorMapping, Signal, SignalError, ESCROW_BOND, ESCROW_COLLECT,
+};
+
+fn signal() -> Signal {
+    Signal {
+        escrow_contract: [1; 20].into(),
+        token_contract: [2; 20].into(),
+        recipient: [3; 20].into(),
+        transfer_amount: U256::from(12345678),
+        reward_amount: U256::from(1234),
+        acknowledgement_url: "https://my-url.com".parse().unwrap(),
+        selector_mapping: Default::default(),
+    }
+}
+
+#[test]
+fn valid_signal() {
+    assert_eq!(signal().validate(), Ok(()));
+}
+
+#[test]
+fn zero_amounts_are_invalid() {
+    let mut s = signal();
+    s.transfer_amount = U256::ZERO;
+    assert_eq!(s.validate(), Err(SignalError::ZeroTransferAmount));
+
+    let mut s = signal();
+    s.reward_amount = U256::ZERO;
+    assert_eq!(s.validate(), Err(SignalError::ZeroRewardAmount));
+}
+
+#[test]
+fn reward_must_be_less_than_transfer() {
+    let mut s = signal();
+    s.reward_amount = s.transfer_amount;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::RewardExceedsTransfer(
+            s.transfer_amount,
+            s.transfer_amount
+        ))
+    );
+}
+
+#[test]
+fn zero_addresses_are_invalid() {
+    let mut s = signal();
+    s.escrow_contract = Address::ZERO;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::ZeroAddress("Escrow contract"))
+    );
+
+    let mut s = signal();
+    s.token_contract = Address::ZERO;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::ZeroAddress("Token contract"))
+    );
+
+    let mut s = signal();
+    s.recipient = Address::ZERO;
+    assert_eq!(s.validate(), Err(SignalError::ZeroAddress("Recipient")));
+}
+
+#[test]
+fn identical_addresses_are_invalid() {
+    let mut s = signal();
+    s.recipient = s.token_contract;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::DuplicateAddress("Token contract", "Recipient"))
+    );
+
+    let mut s = signal();
+    s.recipient = s.escrow_contract;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::DuplicateAddress(
+            "Escrow contract",
+            "Recipient"
+        ))
+    );
+
+    let mut s = signal();
+    s.token_contract = s.escrow_contract;
+    assert_eq!(
+        s.validate(),
+        Err(SignalError::DuplicateAddress(
+            "Escrow contract",
+            "Token contract"
+        ))
+    );
+}
+
+#[test]
+fn selector_remap() {
+    let mapping = SelectorMapping {
+        bond: fixed_bytes!("0x11223344"),
+        ..Default::default()
+    };
+    assert_eq!(mapping.remap(ESCROW_BOND.0), Some([0x11, 0x22, 0x33, 0x44]));
+
+    // Unmapped and unknown selectors can't be translated
+    assert_eq!(mapping.remap(ESCROW_COLLECT.0), None);
+    assert_eq!(mapping.remap([0xaa; 4]), None);
+}
+
+#[test]
+fn selector_apply_to_calldata() {
+    let mapping = SelectorMapping {
+        bond: fixed_bytes!("0x11223344"),
+        ..Default::default()
+    };
+
+    // Only the selector is rewritten, leaving arguments in place
+    let mut data = Bytes::from([ESCROW_BOND.as_slice(), &[7; 32]].concat());
+    assert_eq!(mapping.apply_to_calldata(&mut data), Some(()));
+    assert_eq!(
+        data,
+        Bytes::from([&[0x11, 0x22, 0x33, 0x44], &[7; 32][..]].concat())
+    );
+
+    // Calls without a mapping, and data too short for a selector, are left unchanged
+    let original = Bytes::from([ESCROW_COLLECT.as_slice(), &[7; 32]].concat());
+    let mut data = original.clone();
+    assert_eq!(mapping.apply_to_calldata(&mut data), None);
+    assert_eq!(data, original);
+
+    let mut data = Bytes::from_static(&[1, 2]);
+    assert_eq!(mapping.apply_to_calldata(&mut data), None);
+    assert_eq!(data, Bytes::from_static(&[1, 2]));
+}
+
+#[test]
+fn signal_key_is_order_independent() {
+    assert_eq!(
+        *signal_key(&[1; 32], &[2; 32]),
+        *signal_key(&[2; 32], &[1; 32])
+    );
+    assert_ne!(
+        *signal_key(&[1; 32], &[2; 32]),
+        *signal_key(&[1; 32], &[3; 32])
+    );
+}
+
+#[test]
+fn encrypt_signal() {
+    let signal = signal();
+    let encrypted = EncryptedSignal::encrypt(
+        &signal,
+        "https://relay.com".parse().unwrap(),
+        Bytes::from_static(&[0x13, 0x09]),
+        &[1; 32],
+        &[2; 32],
+        [7; 12],
+    );
+    let json = serde_json::to_vec(&signal).unwrap();
+    assert_eq!(encrypted.token_contract, signal.token_contract);
+    assert_eq!(encrypted.puzzle, Bytes::from_static(&[0x13, 0x09]));
+    // nonce prefix, then the ciphertext and 16 byte tag
+    assert_eq!(encrypted.data[..12], [7; 12]);
+    assert_eq!(encrypted.data.len(), 12 + json.len() + 16);
+}
@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nomad_vm::{Instruction, Program};
+
+/// Number of instructions in the benchmarked puzzle
+const PUZZLE_SIZE: u32 = 50_000;
+
+/// Deterministic mix of instructions, roughly the shape of a compiled puzzle
+fn instruction(i: u32) -> Instruction {
+    let reg = (i % 8) as u8;
+    match i % 5 {
+        0 => Instruction::Set(reg, i.wrapping_mul(0x9E3779B9)),
+        1 => Instruction::Add(reg, (reg + 1) % 8, (reg + 2) % 8),
+        2 => Instruction::Xor(reg, (reg + 3) % 8, (reg + 4) % 8),
+        3 => Instruction::Store(reg, (i % 1024) * 4),
+        _ => Instruction::JmpNe(reg, (reg + 1) % 8, i + 1),
+    }
+}
+
+/// Compare collecting a program before encoding against encoding incrementally
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+
+    group.bench_function("program", |b| {
+        b.iter(|| {
+            let program = Program::from_raw((0..PUZZLE_SIZE).map(instruction).collect());
+            let mut bytecode = Vec::new();
+            program.encode(&mut bytecode).unwrap();
+            black_box(bytecode)
+        })
+    });
+
+    group.bench_function("encoder", |b| {
+        b.iter(|| {
+            let mut encoder = Program::encoder();
+            for i in 0..PUZZLE_SIZE {
+                encoder.push(instruction(i));
+            }
+            black_box(encoder.finish())
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nomad_vm::{program, NomadVm, MEMORY_SIZE};
+
+/// Compare allocating and resetting the sparse memory against a dense buffer
+fn allocation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("allocation");
+    group.sample_size(10);
+
+    group.bench_function("sparse", |b| {
+        b.iter(|| {
+            let mut vm = NomadVm::new(100);
+            vm.execute_program(program![
+                Set 0, 0xDEADBEEF;
+                Store 0, 1000;
+                Load 1, 1000;
+            ])
+            .unwrap();
+            black_box(vm)
+        })
+    });
+
+    group.bench_function("dense", |b| {
+        b.iter(|| {
+            let mut memory = vec![0u8; MEMORY_SIZE];
+            memory[1000..1004].copy_from_slice(&0xDEADBEEFu32.to_be_bytes());
+            memory.fill(0);
+            black_box(memory)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, allocation);
+criterion_main!(benches);
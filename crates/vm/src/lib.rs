@@ -6,18 +6,26 @@ use opentelemetry::Context;
 use thiserror::Error;
 use tracing::trace;
 
+pub use crate::memory::*;
 pub use crate::ops::*;
+pub use crate::output::*;
 pub use crate::program::*;
 
+mod memory;
 mod ops;
+mod output;
 mod program;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
 
-/// Fixed memory size available to the VM
+/// Fixed addressable memory size available to the VM
 pub const MEMORY_SIZE: usize = 1024 * 1024 * 1024;
 /// Number of registers available to the VM
 pub const REGISTERS: usize = 8;
+/// Maximum depth of nested subroutine calls
+pub const MAX_CALL_DEPTH: usize = 256;
 
 /// Type alias for the thread worker socket
 pub type VmSocket = Socket<<NomadVm as Worker>::Request, <NomadVm as Worker>::Response>;
@@ -34,88 +42,267 @@ pub enum VmError {
     InvalidRegister(u8),
     #[error("Invalid program format")]
     InvalidProgram,
+    #[error("Program does not end with a halt instruction")]
+    MissingHalt,
+    #[error("Program requires {0} bytes of memory, exceeding the budget of {1} bytes")]
+    MemoryBudgetExceeded(usize, usize),
+    #[error("Call stack overflow")]
+    StackOverflow,
+    #[error("Return with an empty call stack")]
+    StackUnderflow,
+    #[error("Arithmetic overflow at instruction {0}")]
+    ArithmeticOverflow(usize),
+}
+
+/// Output and metadata from a single program execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionReport {
+    /// 256-bit output concatinated from the registers
+    pub output: [u8; 32],
+    /// Number of instructions executed
+    pub cycles: usize,
+    /// Total gas charged for the executed instructions
+    pub gas: u64,
+    /// True if the program ran to completion (`Halt` or end of program),
+    /// false if execution was cut off by the cycle limit
+    pub halted: bool,
+}
+
+/// Outcome of executing a single instruction with [`NomadVm::step`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+    /// Execution advanced to the next instruction
+    Continued,
+    /// Program hit a `Halt` instruction or ran past the last instruction
+    Halted,
+    /// Control flow moved to the given instruction index
+    Jumped(usize),
 }
 
 /// A simple VM for executing signal puzzles.
 ///
 /// ## Features
 ///
-/// - 1 GiB memory space
+/// - 1 GiB sparse memory space, allocated in pages on demand
 /// - 8x 32-bit registers
-/// - Configurable max cycle count
+/// - Bounded call stack for subroutines
+/// - Gas metering with per-instruction weights, against a configurable limit
 /// - 256-bit program output concatinated from registers
+/// - Wrapping arithmetic, or optionally trapping on overflow
 ///
 /// ## Running as a worker
 ///
 /// A worker can be spawned on a dedicated thread using the helper
 /// method [`NomadVm::spawn`] or by using [`affair`] directly.
 pub struct NomadVm {
-    memory: Vec<u8>,
+    memory: Memory,
     registers: [u32; REGISTERS],
     pc: usize,
+    call_stack: Vec<usize>,
     max_cycles: usize,
+    uniform_weights: bool,
+    validate: bool,
+    trap_on_overflow: bool,
 }
 
+/// Worker requests contain the program bytecode, an optional memory budget
+/// in bytes, and the tracing context of the caller.
 impl Worker for NomadVm {
-    type Request = (Vec<u8>, Context);
+    type Request = (Vec<u8>, Option<usize>, Context);
     type Response = Result<[u8; 32], VmError>;
-    fn handle(&mut self, (program, ctx): Self::Request) -> Self::Response {
+    fn handle(&mut self, (program, memory_budget, ctx): Self::Request) -> Self::Response {
         let span = tracer("nomad").start_with_context("vm", &ctx);
         let _entered = mark_span_as_active(span);
         trace!("Received {} byte program", program.len());
-        self.execute(program)
+        self.reset();
+        match memory_budget {
+            Some(budget) => self.execute_with_budget(program, budget),
+            None => self.execute(program),
+        }
     }
 }
 
 impl NomadVm {
-    /// Create a new VM instance with a given max number of cycles per execution
+    /// Create a new VM instance with a given gas limit per execution.
+    /// Each instruction is charged its [`Instruction::weight`].
     pub fn new(max_cycles: usize) -> Self {
         Self {
-            memory: vec![0u8; MEMORY_SIZE], // 1 GiB
+            memory: Memory::default(),
             registers: [0u32; 8],
             pc: 0,
+            call_stack: Vec::new(),
             max_cycles,
+            uniform_weights: false,
+            validate: false,
+            trap_on_overflow: false,
+        }
+    }
+
+    /// Create a new VM instance where every instruction costs 1 gas, so the
+    /// limit is the max number of instructions executed per execution.
+    pub fn uniform(max_cycles: usize) -> Self {
+        Self {
+            uniform_weights: true,
+            ..Self::new(max_cycles)
         }
     }
 
+    /// Statically validate bytecode with [`Program::validate`] before executing it
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Return [`VmError::ArithmeticOverflow`] when `Add` or `Sub` overflows, instead of wrapping
+    pub fn with_trap_on_overflow(mut self, trap: bool) -> Self {
+        self.trap_on_overflow = trap;
+        self
+    }
+
     /// Spawn a new dedicated thread to run the vm worker on
     pub fn spawn(self) -> VmSocket {
         DedicatedThread::spawn(self)
     }
 
-    /// Parse, validate, and execute raw bytecode, returning the result from the concatinated registers
+    /// Parse, validate, and execute raw bytecode, returning the result from the concatinated registers.
+    ///
+    /// VM state is left intact after execution; call [`NomadVm::reset`] between programs.
     pub fn execute(&mut self, bytecode: Vec<u8>) -> Result<[u8; 32], VmError> {
         let program = Program::from_bytes(&bytecode)?;
+        if self.validate {
+            program.validate()?;
+        }
+        self.execute_program(program)
+    }
+
+    /// Parse and execute raw bytecode, rejecting programs that access memory beyond the given budget.
+    ///
+    /// VM state is left intact after execution; call [`NomadVm::reset`] between programs.
+    pub fn execute_with_budget(
+        &mut self,
+        bytecode: Vec<u8>,
+        memory_budget: usize,
+    ) -> Result<[u8; 32], VmError> {
+        let program = Program::from_bytes(&bytecode)?;
+        if self.validate {
+            program.validate()?;
+        }
+        let required = program.memory_required();
+        if required > memory_budget {
+            return Err(VmError::MemoryBudgetExceeded(required, memory_budget));
+        }
         self.execute_program(program)
     }
 
-    /// Executes a program, resets, and returns the result from the concatinated registers.
+    /// Executes a program and returns the result from the concatinated registers.
+    ///
+    /// VM state is left intact after execution; call [`NomadVm::reset`] between programs.
     pub fn execute_program(&mut self, program: Program) -> Result<[u8; 32], VmError> {
+        self.execute_program_report(program)
+            .map(|report| report.output)
+    }
+
+    /// Executes a program and returns the result along with execution metadata.
+    ///
+    /// VM state is left intact after execution; call [`NomadVm::reset`] between programs.
+    pub fn execute_program_report(&mut self, program: Program) -> Result<ExecutionReport, VmError> {
         // Execute instructions
         let mut cycles = 0;
+        let mut gas = 0;
+        let mut halted = true;
         while let Some(instruction) = program.get(self.pc) {
             if let Err(e) = self.execute_instruction(instruction, program.len()) {
                 println!("{e} - {}", self.pc);
                 return Err(e);
             }
             cycles += 1;
-            if cycles > self.max_cycles || instruction == &Instruction::Halt() {
+            gas += match self.uniform_weights {
+                true => 1,
+                false => instruction.weight(),
+            };
+            if instruction == &Instruction::Halt() {
+                break;
+            }
+            if gas > self.max_cycles as u64 {
+                // Only count as halted if the limit fired on the final instruction
+                halted = program.get(self.pc).is_none();
                 break;
             }
         }
 
         // Compute result from register values
-        let mut result = [0u8; 32];
+        let mut output = [0u8; 32];
         for (i, val) in self.registers.iter().take(8).enumerate() {
             let offset = i * 4;
-            result[offset..offset + 4].copy_from_slice(&val.to_be_bytes());
+            output[offset..offset + 4].copy_from_slice(&val.to_be_bytes());
         }
 
-        // Reset the VM state
-        self.memory.fill(0);
+        Ok(ExecutionReport {
+            output,
+            cycles,
+            gas,
+            halted,
+        })
+    }
+
+    /// Execute exactly one instruction at the current program counter.
+    ///
+    /// Useful for debugging programs by inspecting [`NomadVm::registers`] and
+    /// [`NomadVm::pc`] between steps. Cycle limits are not applied.
+    pub fn step(&mut self, program: &Program) -> Result<StepResult, VmError> {
+        let Some(instruction) = program.get(self.pc) else {
+            return Ok(StepResult::Halted);
+        };
+        if instruction == &Instruction::Halt() {
+            return Ok(StepResult::Halted);
+        }
+
+        let pc = self.pc;
+        self.execute_instruction(instruction, program.len())?;
+        if self.pc == pc + 1 {
+            Ok(StepResult::Continued)
+        } else {
+            Ok(StepResult::Jumped(self.pc))
+        }
+    }
+
+    /// Current register values
+    pub fn registers(&self) -> &[u32; REGISTERS] {
+        &self.registers
+    }
+
+    /// Current program counter (instruction index)
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Reset the VM state, clearing memory, registers, the call stack, and the program counter
+    pub fn reset(&mut self) {
+        self.memory.clear();
         self.registers.fill(0);
+        self.call_stack.clear();
         self.pc = 0;
+    }
 
+    /// Jump to the target if the condition holds, otherwise advance to the next instruction
+    fn branch(&mut self, taken: bool, target: u32, instructions_len: usize) -> Result<(), VmError> {
+        if taken {
+            let target = target as usize;
+            if target >= instructions_len {
+                return Err(VmError::PcOutOfBounds(target));
+            }
+            self.pc = target;
+        } else {
+            self.pc += 1;
+        }
+        Ok(())
+    }
+
+    /// Take the result of an overflowing operation, trapping on overflow if enabled
+    fn overflowing(&self, (result, overflowed): (u32, bool)) -> Result<u32, VmError> {
+        if overflowed && self.trap_on_overflow {
+            return Err(VmError::ArithmeticOverflow(self.pc));
+        }
         Ok(result)
     }
 
@@ -130,18 +317,22 @@ impl NomadVm {
                 self.registers[*reg as usize] = *value;
                 self.pc += 1;
             }
+            Instruction::SetHigh(reg, value) => {
+                let reg = &mut self.registers[*reg as usize];
+                *reg = (*reg & 0x0000FFFF) | ((*value as u32) << 16);
+                self.pc += 1;
+            }
+            Instruction::SetLow(reg, value) => {
+                let reg = &mut self.registers[*reg as usize];
+                *reg = (*reg & 0xFFFF0000) | *value as u32;
+                self.pc += 1;
+            }
             Instruction::Load(reg, addr) => {
                 let addr = *addr as usize;
                 if addr + 3 >= self.memory.len() {
                     return Err(VmError::MemoryOutOfBounds(addr));
                 }
-                let bytes = [
-                    self.memory[addr],
-                    self.memory[addr + 1],
-                    self.memory[addr + 2],
-                    self.memory[addr + 3],
-                ];
-                self.registers[*reg as usize] = u32::from_be_bytes(bytes);
+                self.registers[*reg as usize] = self.memory.read_u32(addr);
                 self.pc += 1;
             }
             Instruction::Store(reg, addr) => {
@@ -149,22 +340,37 @@ impl NomadVm {
                 if addr + 3 >= self.memory.len() {
                     return Err(VmError::MemoryOutOfBounds(addr));
                 }
-                let bytes = self.registers[*reg as usize].to_be_bytes();
-                self.memory[addr] = bytes[0];
-                self.memory[addr + 1] = bytes[1];
-                self.memory[addr + 2] = bytes[2];
-                self.memory[addr + 3] = bytes[3];
+                self.memory.write_u32(addr, self.registers[*reg as usize]);
+                self.pc += 1;
+            }
+            Instruction::Load8(reg, addr) => {
+                let addr = *addr as usize;
+                if addr >= self.memory.len() {
+                    return Err(VmError::MemoryOutOfBounds(addr));
+                }
+                self.registers[*reg as usize] = self.memory.read_u8(addr) as u32;
+                self.pc += 1;
+            }
+            Instruction::Store8(reg, addr) => {
+                let addr = *addr as usize;
+                if addr >= self.memory.len() {
+                    return Err(VmError::MemoryOutOfBounds(addr));
+                }
+                self.memory
+                    .write_u8(addr, self.registers[*reg as usize] as u8);
                 self.pc += 1;
             }
             Instruction::Add(dst, src1, src2) => {
-                let result =
-                    self.registers[*src1 as usize].wrapping_add(self.registers[*src2 as usize]);
+                let result = self.overflowing(
+                    self.registers[*src1 as usize].overflowing_add(self.registers[*src2 as usize]),
+                )?;
                 self.registers[*dst as usize] = result;
                 self.pc += 1;
             }
             Instruction::Sub(dst, src1, src2) => {
-                let result =
-                    self.registers[*src1 as usize].wrapping_sub(self.registers[*src2 as usize]);
+                let result = self.overflowing(
+                    self.registers[*src1 as usize].overflowing_sub(self.registers[*src2 as usize]),
+                )?;
                 self.registers[*dst as usize] = result;
                 self.pc += 1;
             }
@@ -173,35 +379,45 @@ impl NomadVm {
                 self.registers[*dst as usize] = result;
                 self.pc += 1;
             }
-            Instruction::Jmp(target) => {
-                let target = *target as usize;
-                if target >= instructions_len {
-                    return Err(VmError::PcOutOfBounds(target));
-                }
-                self.pc = target;
-            }
+            Instruction::Jmp(target) => self.branch(true, *target, instructions_len)?,
             Instruction::JmpEq(reg1, reg2, target) => {
-                if self.registers[*reg1 as usize] == self.registers[*reg2 as usize] {
-                    let target = *target as usize;
-                    if target >= instructions_len {
-                        return Err(VmError::PcOutOfBounds(target));
-                    }
-                    self.pc = target;
-                } else {
-                    self.pc += 1;
-                }
+                let taken = self.registers[*reg1 as usize] == self.registers[*reg2 as usize];
+                self.branch(taken, *target, instructions_len)?;
             }
             Instruction::JmpNe(reg1, reg2, target) => {
-                if self.registers[*reg1 as usize] != self.registers[*reg2 as usize] {
-                    let target = *target as usize;
-                    if target >= instructions_len {
-                        return Err(VmError::PcOutOfBounds(target));
-                    }
-                    self.pc = target;
-                } else {
-                    self.pc += 1;
+                let taken = self.registers[*reg1 as usize] != self.registers[*reg2 as usize];
+                self.branch(taken, *target, instructions_len)?;
+            }
+            Instruction::JmpLt(reg1, reg2, target) => {
+                let taken = (self.registers[*reg1 as usize] as i32)
+                    < (self.registers[*reg2 as usize] as i32);
+                self.branch(taken, *target, instructions_len)?;
+            }
+            Instruction::JmpGt(reg1, reg2, target) => {
+                let taken = (self.registers[*reg1 as usize] as i32)
+                    > (self.registers[*reg2 as usize] as i32);
+                self.branch(taken, *target, instructions_len)?;
+            }
+            Instruction::JmpLtU(reg1, reg2, target) => {
+                let taken = self.registers[*reg1 as usize] < self.registers[*reg2 as usize];
+                self.branch(taken, *target, instructions_len)?;
+            }
+            Instruction::JmpGtU(reg1, reg2, target) => {
+                let taken = self.registers[*reg1 as usize] > self.registers[*reg2 as usize];
+                self.branch(taken, *target, instructions_len)?;
+            }
+            Instruction::Call(target) => {
+                if self.call_stack.len() >= MAX_CALL_DEPTH {
+                    return Err(VmError::StackOverflow);
                 }
+                let ret = self.pc + 1;
+                self.branch(true, *target, instructions_len)?;
+                self.call_stack.push(ret);
+            }
+            Instruction::Ret() => {
+                self.pc = self.call_stack.pop().ok_or(VmError::StackUnderflow)?;
             }
+            Instruction::Nop() => self.pc += 1,
             Instruction::Print(_bitmap) => {
                 #[cfg(debug_assertions)]
                 {
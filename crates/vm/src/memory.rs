@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::MEMORY_SIZE;
+
+/// Size of a single lazily allocated memory page
+pub const PAGE_SIZE: usize = 4096;
+
+/// Sparse, page-backed memory for the VM.
+///
+/// Pages are only allocated when first written to, and reads from
+/// unmapped pages return zeros. This keeps creating and resetting a
+/// VM cheap regardless of the addressable [`MEMORY_SIZE`].
+#[derive(Default)]
+pub struct Memory {
+    pages: HashMap<usize, Box<[u8; PAGE_SIZE]>>,
+}
+
+impl Memory {
+    /// Total addressable size of the memory in bytes
+    pub fn len(&self) -> usize {
+        MEMORY_SIZE
+    }
+
+    /// Memory always has a non-zero addressable size
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Number of pages currently allocated
+    pub fn allocated_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Read a single byte, returning zero for unmapped pages
+    pub fn read_u8(&self, addr: usize) -> u8 {
+        self.pages
+            .get(&(addr / PAGE_SIZE))
+            .map(|page| page[addr % PAGE_SIZE])
+            .unwrap_or_default()
+    }
+
+    /// Write a single byte, faulting in the page if needed
+    pub fn write_u8(&mut self, addr: usize, value: u8) {
+        let page = self
+            .pages
+            .entry(addr / PAGE_SIZE)
+            .or_insert_with(|| Box::new([0u8; PAGE_SIZE]));
+        page[addr % PAGE_SIZE] = value;
+    }
+
+    /// Read a big-endian 32-bit word. Words may span across pages.
+    pub fn read_u32(&self, addr: usize) -> u32 {
+        u32::from_be_bytes([
+            self.read_u8(addr),
+            self.read_u8(addr + 1),
+            self.read_u8(addr + 2),
+            self.read_u8(addr + 3),
+        ])
+    }
+
+    /// Write a big-endian 32-bit word. Words may span across pages.
+    pub fn write_u32(&mut self, addr: usize, value: u32) {
+        for (i, byte) in value.to_be_bytes().into_iter().enumerate() {
+            self.write_u8(addr + i, byte);
+        }
+    }
+
+    /// Release all allocated pages, zeroing the memory
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
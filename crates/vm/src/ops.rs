@@ -14,6 +14,17 @@ pub enum Opcode {
     Jmp = 0x06,
     JmpEq = 0x07,
     JmpNe = 0x08,
+    JmpLt = 0x09,
+    JmpGt = 0x0A,
+    JmpLtU = 0x0B,
+    JmpGtU = 0x0C,
+    Call = 0x0D,
+    Ret = 0x0E,
+    Load8 = 0x0F,
+    Store8 = 0x10,
+    SetHigh = 0x11,
+    SetLow = 0x12,
+    Nop = 0x13,
     Print = 0xFE,
     Halt = 0xFF,
 }
@@ -21,17 +32,28 @@ pub enum Opcode {
 impl Opcode {
     pub fn size(&self) -> usize {
         match self {
-            Opcode::Set => 1 + 1 + 4,       // opcode + reg + value
-            Opcode::Load => 1 + 1 + 4,      // opcode + reg + addr
-            Opcode::Store => 1 + 1 + 4,     // opcode + reg + addr
-            Opcode::Add => 1 + 1 + 1 + 1,   // opcode + dst_reg + src1_reg + src2_reg
-            Opcode::Sub => 1 + 1 + 1 + 1,   // opcode + dst_reg + src1_reg + src2_reg
-            Opcode::Xor => 1 + 1 + 1 + 1,   // opcode + dst_reg + src1_reg + src2_reg
-            Opcode::Jmp => 1 + 4,           // opcode + target
-            Opcode::JmpEq => 1 + 1 + 1 + 4, // opcode + reg1 + reg2 + target
-            Opcode::JmpNe => 1 + 1 + 1 + 4, // opcode + reg1 + reg2 + target
-            Opcode::Print => 1 + 1,         // opcode + bitmap
-            Opcode::Halt => 1,              // opcode only
+            Opcode::Set => 1 + 1 + 4,        // opcode + reg + value
+            Opcode::Load => 1 + 1 + 4,       // opcode + reg + addr
+            Opcode::Store => 1 + 1 + 4,      // opcode + reg + addr
+            Opcode::Add => 1 + 1 + 1 + 1,    // opcode + dst_reg + src1_reg + src2_reg
+            Opcode::Sub => 1 + 1 + 1 + 1,    // opcode + dst_reg + src1_reg + src2_reg
+            Opcode::Xor => 1 + 1 + 1 + 1,    // opcode + dst_reg + src1_reg + src2_reg
+            Opcode::Jmp => 1 + 4,            // opcode + target
+            Opcode::JmpEq => 1 + 1 + 1 + 4,  // opcode + reg1 + reg2 + target
+            Opcode::JmpNe => 1 + 1 + 1 + 4,  // opcode + reg1 + reg2 + target
+            Opcode::JmpLt => 1 + 1 + 1 + 4,  // opcode + reg1 + reg2 + target
+            Opcode::JmpGt => 1 + 1 + 1 + 4,  // opcode + reg1 + reg2 + target
+            Opcode::JmpLtU => 1 + 1 + 1 + 4, // opcode + reg1 + reg2 + target
+            Opcode::JmpGtU => 1 + 1 + 1 + 4, // opcode + reg1 + reg2 + target
+            Opcode::Call => 1 + 4,           // opcode + target
+            Opcode::Ret => 1,                // opcode only
+            Opcode::Load8 => 1 + 1 + 4,      // opcode + reg + addr
+            Opcode::Store8 => 1 + 1 + 4,     // opcode + reg + addr
+            Opcode::SetHigh => 1 + 1 + 2,    // opcode + reg + value
+            Opcode::SetLow => 1 + 1 + 2,     // opcode + reg + value
+            Opcode::Nop => 1,                // opcode only
+            Opcode::Print => 1 + 1,          // opcode + bitmap
+            Opcode::Halt => 1,               // opcode only
         }
     }
 }
@@ -50,6 +72,17 @@ impl TryFrom<u8> for Opcode {
             0x06 => Ok(Opcode::Jmp),
             0x07 => Ok(Opcode::JmpEq),
             0x08 => Ok(Opcode::JmpNe),
+            0x09 => Ok(Opcode::JmpLt),
+            0x0A => Ok(Opcode::JmpGt),
+            0x0B => Ok(Opcode::JmpLtU),
+            0x0C => Ok(Opcode::JmpGtU),
+            0x0D => Ok(Opcode::Call),
+            0x0E => Ok(Opcode::Ret),
+            0x0F => Ok(Opcode::Load8),
+            0x10 => Ok(Opcode::Store8),
+            0x11 => Ok(Opcode::SetHigh),
+            0x12 => Ok(Opcode::SetLow),
+            0x13 => Ok(Opcode::Nop),
             0xFE => Ok(Opcode::Print),
             0xFF => Ok(Opcode::Halt),
             _ => Err(VmError::InvalidInstruction(value)),
@@ -75,6 +108,30 @@ pub enum Instruction {
     /// ```
     Set(u8, u32),
 
+    /// Set the upper 16 bits of a register, leaving the lower 16 bits unchanged.
+    ///
+    /// # Arguments
+    /// * `u8` - Target register (0-7)
+    /// * `u16` - Value for the upper half
+    ///
+    /// # Example
+    /// ```ignore
+    /// SetHigh(0, 0xDEAD) // reg[0] = 0xDEAD0000 | (reg[0] & 0xFFFF)
+    /// ```
+    SetHigh(u8, u16),
+
+    /// Set the lower 16 bits of a register, leaving the upper 16 bits unchanged.
+    ///
+    /// # Arguments
+    /// * `u8` - Target register (0-7)
+    /// * `u16` - Value for the lower half
+    ///
+    /// # Example
+    /// ```ignore
+    /// SetLow(0, 0xBEEF) // reg[0] = (reg[0] & 0xFFFF0000) | 0xBEEF
+    /// ```
+    SetLow(u8, u16),
+
     /// Load a 32-bit value from memory into a register.
     ///
     /// # Arguments
@@ -99,6 +156,30 @@ pub enum Instruction {
     /// ```
     Store(u8, u32),
 
+    /// Load a single byte from memory into a register, zero-extending it.
+    ///
+    /// # Arguments
+    /// * `u8` - Target register (0-7)
+    /// * `u32` - Memory address to load from
+    ///
+    /// # Example
+    /// ```ignore
+    /// Load8(1, 0x1000) // Loads the byte at address 0x1000 into register 1
+    /// ```
+    Load8(u8, u32),
+
+    /// Store the low byte of a register to memory.
+    ///
+    /// # Arguments
+    /// * `u8` - Source register (0-7)
+    /// * `u32` - Memory address to store to
+    ///
+    /// # Example
+    /// ```ignore
+    /// Store8(2, 0x2000) // Stores the low byte of register 2 to address 0x2000
+    /// ```
+    Store8(u8, u32),
+
     /// Add two registers and store the result.
     ///
     /// # Arguments
@@ -175,6 +256,94 @@ pub enum Instruction {
     /// ```
     JmpNe(u8, u8, u32),
 
+    /// Conditional jump if the first register is less than the second,
+    /// comparing both as signed 32-bit integers.
+    ///
+    /// # Arguments
+    /// * `u8` - First register to compare (0-7)
+    /// * `u8` - Second register to compare (0-7)
+    /// * `u32` - Target instruction index if less than
+    ///
+    /// # Example
+    /// ```ignore
+    /// JmpLt(0, 1, 5) // Jump to instruction 5 if reg[0] as i32 < reg[1] as i32
+    /// ```
+    JmpLt(u8, u8, u32),
+
+    /// Conditional jump if the first register is greater than the second,
+    /// comparing both as signed 32-bit integers.
+    ///
+    /// # Arguments
+    /// * `u8` - First register to compare (0-7)
+    /// * `u8` - Second register to compare (0-7)
+    /// * `u32` - Target instruction index if greater than
+    ///
+    /// # Example
+    /// ```ignore
+    /// JmpGt(0, 1, 5) // Jump to instruction 5 if reg[0] as i32 > reg[1] as i32
+    /// ```
+    JmpGt(u8, u8, u32),
+
+    /// Conditional jump if the first register is less than the second,
+    /// comparing both as unsigned 32-bit integers.
+    ///
+    /// # Arguments
+    /// * `u8` - First register to compare (0-7)
+    /// * `u8` - Second register to compare (0-7)
+    /// * `u32` - Target instruction index if less than
+    ///
+    /// # Example
+    /// ```ignore
+    /// JmpLtU(0, 1, 5) // Jump to instruction 5 if reg[0] < reg[1]
+    /// ```
+    JmpLtU(u8, u8, u32),
+
+    /// Conditional jump if the first register is greater than the second,
+    /// comparing both as unsigned 32-bit integers.
+    ///
+    /// # Arguments
+    /// * `u8` - First register to compare (0-7)
+    /// * `u8` - Second register to compare (0-7)
+    /// * `u32` - Target instruction index if greater than
+    ///
+    /// # Example
+    /// ```ignore
+    /// JmpGtU(0, 1, 5) // Jump to instruction 5 if reg[0] > reg[1]
+    /// ```
+    JmpGtU(u8, u8, u32),
+
+    /// Call a subroutine, pushing the next instruction index onto the
+    /// call stack and jumping to the target.
+    ///
+    /// # Arguments
+    /// * `u32` - Target instruction index of the subroutine
+    ///
+    /// # Example
+    /// ```ignore
+    /// Call(10) // Jump to instruction 10, returning to the next instruction on `Ret`
+    /// ```
+    Call(u32),
+
+    /// Return from a subroutine, popping the return address from the call stack.
+    ///
+    /// No arguments required.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Ret // Jump back to the instruction after the matching `Call`
+    /// ```
+    Ret(),
+
+    /// Do nothing, only advancing to the next instruction.
+    ///
+    /// No arguments required.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Nop // Padding without side effects
+    /// ```
+    Nop(),
+
     /// Debug print register values.
     ///
     /// Prints the values of the specified registers to stdout in debug builds.
@@ -205,14 +374,25 @@ impl Display for Instruction {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
             Instruction::Set(reg, val) => write!(f, "SET   R{reg}, 0x{val:08X}"),
+            Instruction::SetHigh(reg, val) => write!(f, "SETHI R{reg}, 0x{val:04X}"),
+            Instruction::SetLow(reg, val) => write!(f, "SETLO R{reg}, 0x{val:04X}"),
             Instruction::Add(dst, src1, src2) => write!(f, "ADD   R{dst}, R{src1}, R{src2}"),
             Instruction::Sub(dst, src1, src2) => write!(f, "SUB   R{dst}, R{src1}, R{src2}"),
             Instruction::Xor(dst, src1, src2) => write!(f, "XOR   R{dst}, R{src1}, R{src2}"),
             Instruction::Load(reg, addr) => write!(f, "LOAD  R{reg}, 0x{addr:08X}"),
             Instruction::Store(reg, addr) => write!(f, "STORE R{reg}, 0x{addr:08X}"),
+            Instruction::Load8(reg, addr) => write!(f, "LOAD8 R{reg}, 0x{addr:08X}"),
+            Instruction::Store8(reg, addr) => write!(f, "STORE8 R{reg}, 0x{addr:08X}"),
             Instruction::Jmp(addr) => write!(f, "JMP   0x{addr:08X}"),
             Instruction::JmpEq(r1, r2, addr) => write!(f, "JMPEQ R{r1}, R{r2}, 0x{addr:08X}"),
             Instruction::JmpNe(r1, r2, addr) => write!(f, "JMPNE R{r1}, R{r2}, 0x{addr:08X}"),
+            Instruction::JmpLt(r1, r2, addr) => write!(f, "JMPLT R{r1}, R{r2}, 0x{addr:08X}"),
+            Instruction::JmpGt(r1, r2, addr) => write!(f, "JMPGT R{r1}, R{r2}, 0x{addr:08X}"),
+            Instruction::JmpLtU(r1, r2, addr) => write!(f, "JMPLTU R{r1}, R{r2}, 0x{addr:08X}"),
+            Instruction::JmpGtU(r1, r2, addr) => write!(f, "JMPGTU R{r1}, R{r2}, 0x{addr:08X}"),
+            Instruction::Call(addr) => write!(f, "CALL  0x{addr:08X}"),
+            Instruction::Ret() => write!(f, "RET   -"),
+            Instruction::Nop() => write!(f, "NOP   -"),
             Instruction::Print(bitmap) => {
                 let mut reg_list = Vec::new();
                 for reg_idx in 0..8u8 {
@@ -227,7 +407,7 @@ impl Display for Instruction {
     }
 }
 
-fn validate_reg(reg: u8) -> Result<u8, VmError> {
+pub(crate) fn validate_reg(reg: u8) -> Result<u8, VmError> {
     if reg >= REGISTERS as u8 {
         Err(VmError::InvalidRegister(reg))
     } else {
@@ -239,19 +419,117 @@ impl Instruction {
     pub fn size(&self) -> usize {
         match self {
             Instruction::Set { .. } => 6,
+            Instruction::SetHigh { .. } => 4,
+            Instruction::SetLow { .. } => 4,
             Instruction::Load { .. } => 6,
             Instruction::Store { .. } => 6,
+            Instruction::Load8 { .. } => 6,
+            Instruction::Store8 { .. } => 6,
             Instruction::Add { .. } => 4,
             Instruction::Sub { .. } => 4,
             Instruction::Xor { .. } => 4,
             Instruction::Jmp { .. } => 5,
             Instruction::JmpEq { .. } => 7,
             Instruction::JmpNe { .. } => 7,
+            Instruction::JmpLt { .. } => 7,
+            Instruction::JmpGt { .. } => 7,
+            Instruction::JmpLtU { .. } => 7,
+            Instruction::JmpGtU { .. } => 7,
+            Instruction::Call { .. } => 5,
+            Instruction::Ret() => 1,
+            Instruction::Nop() => 1,
             Instruction::Print(_) => 2,
             Instruction::Halt() => 1,
         }
     }
 
+    /// Mnemonic used when disassembling the instruction
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Set(..) => "SET",
+            Instruction::SetHigh(..) => "SETHI",
+            Instruction::SetLow(..) => "SETLO",
+            Instruction::Load(..) => "LOAD",
+            Instruction::Store(..) => "STORE",
+            Instruction::Load8(..) => "LOAD8",
+            Instruction::Store8(..) => "STORE8",
+            Instruction::Add(..) => "ADD",
+            Instruction::Sub(..) => "SUB",
+            Instruction::Xor(..) => "XOR",
+            Instruction::Jmp(..) => "JMP",
+            Instruction::JmpEq(..) => "JMPEQ",
+            Instruction::JmpNe(..) => "JMPNE",
+            Instruction::JmpLt(..) => "JMPLT",
+            Instruction::JmpGt(..) => "JMPGT",
+            Instruction::JmpLtU(..) => "JMPLTU",
+            Instruction::JmpGtU(..) => "JMPGTU",
+            Instruction::Call(..) => "CALL",
+            Instruction::Ret() => "RET",
+            Instruction::Nop() => "NOP",
+            Instruction::Print(..) => "PRINT",
+            Instruction::Halt() => "HALT",
+        }
+    }
+
+    /// Gas charged for executing the instruction.
+    ///
+    /// Register and control flow operations cost 1, subroutine calls cost 2 for
+    /// the call stack, and memory operations cost 4 since they may touch or
+    /// allocate a page.
+    pub fn weight(&self) -> u64 {
+        match self {
+            Instruction::Load(..)
+            | Instruction::Store(..)
+            | Instruction::Load8(..)
+            | Instruction::Store8(..) => 4,
+            Instruction::Call(..) | Instruction::Ret() => 2,
+            _ => 1,
+        }
+    }
+
+    /// Register operands used by the instruction
+    pub fn registers(&self) -> Vec<u8> {
+        match self {
+            Instruction::Set(reg, _)
+            | Instruction::SetHigh(reg, _)
+            | Instruction::SetLow(reg, _)
+            | Instruction::Load(reg, _)
+            | Instruction::Store(reg, _)
+            | Instruction::Load8(reg, _)
+            | Instruction::Store8(reg, _) => vec![*reg],
+            Instruction::Add(dst, src1, src2)
+            | Instruction::Sub(dst, src1, src2)
+            | Instruction::Xor(dst, src1, src2) => vec![*dst, *src1, *src2],
+            Instruction::JmpEq(reg1, reg2, _)
+            | Instruction::JmpNe(reg1, reg2, _)
+            | Instruction::JmpLt(reg1, reg2, _)
+            | Instruction::JmpGt(reg1, reg2, _)
+            | Instruction::JmpLtU(reg1, reg2, _)
+            | Instruction::JmpGtU(reg1, reg2, _) => vec![*reg1, *reg2],
+            Instruction::Jmp(_)
+            | Instruction::Call(_)
+            | Instruction::Ret()
+            | Instruction::Nop()
+            | Instruction::Print(_)
+            | Instruction::Halt() => Vec::new(),
+        }
+    }
+
+    /// Target instruction index if this is a jump
+    pub fn jump_target(&self) -> Option<u32> {
+        match self {
+            Instruction::Jmp(target)
+            | Instruction::Call(target)
+            | Instruction::JmpEq(_, _, target)
+            | Instruction::JmpNe(_, _, target)
+            | Instruction::JmpLt(_, _, target)
+            | Instruction::JmpGt(_, _, target)
+            | Instruction::JmpLtU(_, _, target)
+            | Instruction::JmpGtU(_, _, target) => Some(*target),
+            _ => None,
+        }
+    }
+
     /// Parse an instruction from a byte slice, returning the instruction
     /// and number of bytes consumed if valid.
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), VmError> {
@@ -270,6 +548,14 @@ impl Instruction {
                 validate_reg(bytes[1])?,
                 u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
             ),
+            Opcode::SetHigh => Instruction::SetHigh(
+                validate_reg(bytes[1])?,
+                u16::from_be_bytes([bytes[2], bytes[3]]),
+            ),
+            Opcode::SetLow => Instruction::SetLow(
+                validate_reg(bytes[1])?,
+                u16::from_be_bytes([bytes[2], bytes[3]]),
+            ),
             Opcode::Load => Instruction::Load(
                 validate_reg(bytes[1])?,
                 u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
@@ -278,6 +564,14 @@ impl Instruction {
                 validate_reg(bytes[1])?,
                 u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
             ),
+            Opcode::Load8 => Instruction::Load8(
+                validate_reg(bytes[1])?,
+                u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
+            ),
+            Opcode::Store8 => Instruction::Store8(
+                validate_reg(bytes[1])?,
+                u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
+            ),
             Opcode::Add => Instruction::Add(
                 validate_reg(bytes[1])?,
                 validate_reg(bytes[2])?,
@@ -306,6 +600,31 @@ impl Instruction {
                 validate_reg(bytes[2])?,
                 u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
             ),
+            Opcode::JmpLt => Instruction::JmpLt(
+                validate_reg(bytes[1])?,
+                validate_reg(bytes[2])?,
+                u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            ),
+            Opcode::JmpGt => Instruction::JmpGt(
+                validate_reg(bytes[1])?,
+                validate_reg(bytes[2])?,
+                u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            ),
+            Opcode::JmpLtU => Instruction::JmpLtU(
+                validate_reg(bytes[1])?,
+                validate_reg(bytes[2])?,
+                u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            ),
+            Opcode::JmpGtU => Instruction::JmpGtU(
+                validate_reg(bytes[1])?,
+                validate_reg(bytes[2])?,
+                u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            ),
+            Opcode::Call => {
+                Instruction::Call(u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]))
+            }
+            Opcode::Ret => Instruction::Ret(),
+            Opcode::Nop => Instruction::Nop(),
             Opcode::Print => {
                 let bitmap = bytes[1];
                 if bitmap == 0 {
@@ -329,6 +648,20 @@ impl Instruction {
                 buf[2..6].copy_from_slice(&value.to_be_bytes());
                 writer.write_all(&buf)?;
             }
+            Instruction::SetHigh(reg, value) => {
+                let mut buf = [0u8; 4];
+                buf[0] = Opcode::SetHigh as u8;
+                buf[1] = *reg;
+                buf[2..4].copy_from_slice(&value.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::SetLow(reg, value) => {
+                let mut buf = [0u8; 4];
+                buf[0] = Opcode::SetLow as u8;
+                buf[1] = *reg;
+                buf[2..4].copy_from_slice(&value.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
             Instruction::Load(reg, addr) => {
                 let mut buf = [0u8; 6];
                 buf[0] = Opcode::Load as u8;
@@ -343,6 +676,20 @@ impl Instruction {
                 buf[2..6].copy_from_slice(&addr.to_be_bytes());
                 writer.write_all(&buf)?;
             }
+            Instruction::Load8(reg, addr) => {
+                let mut buf = [0u8; 6];
+                buf[0] = Opcode::Load8 as u8;
+                buf[1] = *reg;
+                buf[2..6].copy_from_slice(&addr.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::Store8(reg, addr) => {
+                let mut buf = [0u8; 6];
+                buf[0] = Opcode::Store8 as u8;
+                buf[1] = *reg;
+                buf[2..6].copy_from_slice(&addr.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
             Instruction::Add(dst, src1, src2) => {
                 let buf = [Opcode::Add as u8, *dst, *src1, *src2];
                 writer.write_all(&buf)?;
@@ -377,6 +724,50 @@ impl Instruction {
                 buf[3..7].copy_from_slice(&target.to_be_bytes());
                 writer.write_all(&buf)?;
             }
+            Instruction::JmpLt(reg1, reg2, target) => {
+                let mut buf = [0u8; 7];
+                buf[0] = Opcode::JmpLt as u8;
+                buf[1] = *reg1;
+                buf[2] = *reg2;
+                buf[3..7].copy_from_slice(&target.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::JmpGt(reg1, reg2, target) => {
+                let mut buf = [0u8; 7];
+                buf[0] = Opcode::JmpGt as u8;
+                buf[1] = *reg1;
+                buf[2] = *reg2;
+                buf[3..7].copy_from_slice(&target.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::JmpLtU(reg1, reg2, target) => {
+                let mut buf = [0u8; 7];
+                buf[0] = Opcode::JmpLtU as u8;
+                buf[1] = *reg1;
+                buf[2] = *reg2;
+                buf[3..7].copy_from_slice(&target.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::JmpGtU(reg1, reg2, target) => {
+                let mut buf = [0u8; 7];
+                buf[0] = Opcode::JmpGtU as u8;
+                buf[1] = *reg1;
+                buf[2] = *reg2;
+                buf[3..7].copy_from_slice(&target.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::Call(target) => {
+                let mut buf = [0u8; 5];
+                buf[0] = Opcode::Call as u8;
+                buf[1..5].copy_from_slice(&target.to_be_bytes());
+                writer.write_all(&buf)?;
+            }
+            Instruction::Ret() => {
+                writer.write_all(&[Opcode::Ret as u8])?;
+            }
+            Instruction::Nop() => {
+                writer.write_all(&[Opcode::Nop as u8])?;
+            }
             Instruction::Print(bitmap) => {
                 let buf = [Opcode::Print as u8, *bitmap];
                 writer.write_all(&buf)?;
@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::REGISTERS;
+
+/// 256-bit program output concatinated from the registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VmOutput(pub [u8; 32]);
+
+impl From<[u8; 32]> for VmOutput {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl VmOutput {
+    /// Split the output back into register values
+    pub fn registers(&self) -> [u32; REGISTERS] {
+        let mut registers = [0u32; REGISTERS];
+        for (i, chunk) in self.0.chunks_exact(4).enumerate() {
+            registers[i] = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+        registers
+    }
+
+    /// Compare against an expected output, returning the registers that differ (if any)
+    pub fn diff(&self, expected: impl Into<VmOutput>) -> Option<OutputDiff> {
+        let expected = expected.into().registers();
+        let registers = self
+            .registers()
+            .into_iter()
+            .zip(expected)
+            .enumerate()
+            .filter(|(_, (actual, expected))| actual != expected)
+            .map(|(register, (actual, expected))| RegisterDiff {
+                register,
+                actual,
+                expected,
+            })
+            .collect::<Vec<_>>();
+        (!registers.is_empty()).then_some(OutputDiff { registers })
+    }
+}
+
+/// Registers which differ between two outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDiff {
+    pub registers: Vec<RegisterDiff>,
+}
+
+/// A single mismatched register value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterDiff {
+    pub register: usize,
+    pub actual: u32,
+    pub expected: u32,
+}
+
+impl Display for OutputDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, diff) in self.registers.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(
+                f,
+                "R{}: expected 0x{:08X}, got 0x{:08X}",
+                diff.register, diff.expected, diff.actual
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
     io::{Result as IoResult, Write},
     ops::Deref,
 };
 
-use crate::{Instruction, VmError};
+use crate::{validate_reg, Instruction, VmError};
 
 /// Construct an unvalidated program from raw mnemonics
 ///
@@ -34,6 +35,7 @@ macro_rules! program {
     };
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program(Vec<Instruction>);
 
 impl Deref for Program {
@@ -65,6 +67,78 @@ impl Program {
         Ok(Program(instructions))
     }
 
+    /// Parse program bytecode, rejecting bytecode longer than `max_len` bytes before parsing.
+    pub fn from_bytes_limited(bytes: &[u8], max_len: usize) -> Result<Program, VmError> {
+        if bytes.len() > max_len {
+            return Err(VmError::InvalidProgram);
+        }
+        Self::from_bytes(bytes)
+    }
+
+    /// Create an encoder to write program bytecode one instruction at a time,
+    /// without collecting the instructions first.
+    pub fn encoder() -> ProgramEncoder {
+        ProgramEncoder::default()
+    }
+
+    /// Size of the encoded program bytecode in bytes
+    pub fn encoded_len(&self) -> usize {
+        self.0.iter().map(Instruction::size).sum()
+    }
+
+    /// Statically validate the program before execution. Checks:
+    /// - every jump target is within the program
+    /// - every register operand is valid
+    /// - the program ends with a `Halt` instruction
+    pub fn validate(&self) -> Result<(), VmError> {
+        for instruction in &self.0 {
+            for reg in instruction.registers() {
+                validate_reg(reg)?;
+            }
+            if let Some(target) = instruction.jump_target() {
+                if target as usize >= self.0.len() {
+                    return Err(VmError::PcOutOfBounds(target as usize));
+                }
+            }
+        }
+        if self.0.last() != Some(&Instruction::Halt()) {
+            return Err(VmError::MissingHalt);
+        }
+        Ok(())
+    }
+
+    /// Number of bytes of memory the program may access, computed from the
+    /// highest address used by any memory instruction.
+    pub fn memory_required(&self) -> usize {
+        self.0
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Load(_, addr) | Instruction::Store(_, addr) => {
+                    Some(*addr as usize + 4)
+                }
+                Instruction::Load8(_, addr) | Instruction::Store8(_, addr) => {
+                    Some(*addr as usize + 1)
+                }
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Count of each instruction in the program, keyed by mnemonic
+    pub fn opcode_histogram(&self) -> HashMap<&'static str, usize> {
+        let mut histogram = HashMap::new();
+        for instruction in &self.0 {
+            *histogram.entry(instruction.mnemonic()).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// Render the program as human-readable mnemonics, one indexed instruction per line.
+    pub fn disassemble(&self) -> String {
+        self.to_string()
+    }
+
     /// Write the program bytecode into a given buffer.
     pub fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
         for instruction in &self.0 {
@@ -74,6 +148,36 @@ impl Program {
     }
 }
 
+/// Incremental program bytecode encoder, created with [`Program::encoder`]
+#[derive(Debug, Default)]
+pub struct ProgramEncoder {
+    bytecode: Vec<u8>,
+}
+
+impl ProgramEncoder {
+    /// Append an encoded instruction to the bytecode
+    pub fn push(&mut self, instruction: Instruction) {
+        instruction
+            .encode(&mut self.bytecode)
+            .expect("writing to a vec is infallible");
+    }
+
+    /// Number of bytes encoded so far
+    pub fn len(&self) -> usize {
+        self.bytecode.len()
+    }
+
+    /// Returns true if no instructions have been encoded
+    pub fn is_empty(&self) -> bool {
+        self.bytecode.is_empty()
+    }
+
+    /// Finish encoding, returning the program bytecode
+    pub fn finish(self) -> Vec<u8> {
+        self.bytecode
+    }
+}
+
 impl Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (i, inst) in self.0.iter().enumerate() {
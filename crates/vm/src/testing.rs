@@ -0,0 +1,72 @@
+//! Utilities for fuzzing the vm with random programs
+
+use rand::Rng;
+
+use crate::{Instruction, NomadVm, Program, REGISTERS};
+
+/// Highest memory address used by random programs, keeping memory usage small
+const MAX_ADDRESS: u32 = 4096;
+
+/// Generate a random program of `len` instructions, ending with `Halt`.
+///
+/// All registers are valid and all jump targets are within the program, but
+/// programs may still loop until the cycle limit or misuse the call stack.
+pub fn random_program(rng: &mut impl Rng, len: usize) -> Program {
+    assert!(len > 0, "programs must contain at least one instruction");
+    let mut instructions = (1..len)
+        .map(|_| random_instruction(rng, len as u32))
+        .collect::<Vec<_>>();
+    instructions.push(Instruction::Halt());
+    Program::from_raw(instructions)
+}
+
+/// Run a program from its encoded bytecode and directly, asserting both runs
+/// produce the same output or error.
+pub fn assert_equivalent(program: &Program, max_cycles: usize) {
+    let mut bytecode = Vec::new();
+    program.encode(&mut bytecode).unwrap();
+    let from_bytes = NomadVm::new(max_cycles).execute(bytecode);
+    let direct = NomadVm::new(max_cycles).execute_program(program.clone());
+    match (from_bytes, direct) {
+        (Ok(a), Ok(b)) => assert_eq!(a, b, "output mismatch for program:\n{program}"),
+        (Err(a), Err(b)) => assert_eq!(
+            a.to_string(),
+            b.to_string(),
+            "error mismatch for program:\n{program}"
+        ),
+        (a, b) => panic!("result mismatch ({a:?} != {b:?}) for program:\n{program}"),
+    }
+}
+
+fn random_instruction(rng: &mut impl Rng, len: u32) -> Instruction {
+    let (a, b, c) = (reg(rng), reg(rng), reg(rng));
+    let addr = rng.random_range(0..MAX_ADDRESS);
+    let target = rng.random_range(0..len);
+    match rng.random_range(0..21) {
+        0 => Instruction::Set(a, rng.random()),
+        1 => Instruction::SetHigh(a, rng.random()),
+        2 => Instruction::SetLow(a, rng.random()),
+        3 => Instruction::Load(a, addr),
+        4 => Instruction::Store(a, addr),
+        5 => Instruction::Load8(a, addr),
+        6 => Instruction::Store8(a, addr),
+        7 => Instruction::Add(a, b, c),
+        8 => Instruction::Sub(a, b, c),
+        9 => Instruction::Xor(a, b, c),
+        10 => Instruction::Jmp(target),
+        11 => Instruction::JmpEq(a, b, target),
+        12 => Instruction::JmpNe(a, b, target),
+        13 => Instruction::JmpLt(a, b, target),
+        14 => Instruction::JmpGt(a, b, target),
+        15 => Instruction::JmpLtU(a, b, target),
+        16 => Instruction::JmpGtU(a, b, target),
+        17 => Instruction::Call(target),
+        18 => Instruction::Ret(),
+        19 => Instruction::Nop(),
+        _ => Instruction::Halt(),
+    }
+}
+
+fn reg(rng: &mut impl Rng) -> u8 {
+    rng.random_range(0..REGISTERS as u8)
+}
@@ -12,6 +12,36 @@ fn test_basic_arithmetic() -> Result<(), VmError> {
     Ok(())
 }
 
+#[test]
+fn test_set_high_low() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        SetHigh 0, 0xDEAD;
+        Set 1, 0x12345678;
+        SetLow 0, 0xBEEF;
+        SetLow 1, 0xABCD;
+        SetHigh 1, 0x0000;
+    ])?;
+    assert_eq!(res[0..4], 0xDEADBEEFu32.to_be_bytes());
+    assert_eq!(res[4..8], 0x0000ABCDu32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_nop() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let report = vm.execute_program_report(program![
+        Nop;
+        Nop;
+        Nop;
+        Halt;
+    ])?;
+    assert_eq!(report.output, [0; 32]);
+    assert_eq!(report.cycles, 4);
+    assert_eq!(vm.pc(), 3);
+    Ok(())
+}
+
 #[test]
 fn test_addition() -> Result<(), VmError> {
     let mut vm = NomadVm::new(100);
@@ -36,6 +66,47 @@ fn test_subtraction() -> Result<(), VmError> {
     Ok(())
 }
 
+#[test]
+fn test_addition_wraps_by_default() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF;
+        Set 1, 1;
+        Add 2, 0, 1;
+        Sub 3, 2, 1;
+    ])?;
+    assert_eq!(res[8..12], 0u32.to_be_bytes());
+    assert_eq!(res[12..16], 0xFFFFFFFFu32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_trap_on_overflow() {
+    let mut vm = NomadVm::new(100).with_trap_on_overflow(true);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF;
+        Set 1, 1;
+        Add 2, 0, 1;
+    ]);
+    assert!(matches!(res, Err(VmError::ArithmeticOverflow(2))));
+
+    vm.reset();
+    let res = vm.execute_program(program![
+        Set 1, 1;
+        Sub 2, 0, 1;
+    ]);
+    assert!(matches!(res, Err(VmError::ArithmeticOverflow(1))));
+
+    // Results in range are unaffected
+    vm.reset();
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFE;
+        Set 1, 1;
+        Add 2, 0, 1;
+    ]);
+    assert_eq!(res.unwrap()[8..12], 0xFFFFFFFFu32.to_be_bytes());
+}
+
 #[test]
 fn test_xor() -> Result<(), VmError> {
     let mut vm = NomadVm::new(100);
@@ -60,6 +131,24 @@ fn test_memory_load_store() -> Result<(), VmError> {
     Ok(())
 }
 
+#[test]
+fn test_memory_load8_store8() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xDEADBEEF;
+        Store 0, 1000;
+        Load8 1, 1000;
+        Set 2, 0x12345642;
+        Store8 2, 1003;
+        Load 3, 1000;
+        Load8 4, 1003;
+    ])?;
+    assert_eq!(res[4..8], 0xDEu32.to_be_bytes());
+    assert_eq!(res[12..16], 0xDEADBE42u32.to_be_bytes());
+    assert_eq!(res[16..20], 0x42u32.to_be_bytes());
+    Ok(())
+}
+
 #[test]
 fn test_unconditional_jump() -> Result<(), VmError> {
     let mut vm = NomadVm::new(100);
@@ -128,6 +217,118 @@ fn test_conditional_jump_ne_false() -> Result<(), VmError> {
     Ok(())
 }
 
+#[test]
+fn test_conditional_jump_lt_signed() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF; // -1
+        Set 1, 1;
+        JmpLt 0, 1, 4;
+        Set 2, 1;
+        Set 2, 2;
+    ])?;
+    assert_eq!(res[8..12], 2u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_conditional_jump_gt_signed() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF; // -1
+        Set 1, 1;
+        JmpGt 0, 1, 5;
+        Set 2, 1;
+    ])?;
+    assert_eq!(res[8..12], 1u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_conditional_jump_lt_unsigned() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF;
+        Set 1, 1;
+        JmpLtU 0, 1, 5;
+        Set 2, 1;
+    ])?;
+    assert_eq!(res[8..12], 1u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_conditional_jump_gt_unsigned() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF;
+        Set 1, 1;
+        JmpGtU 0, 1, 4;
+        Set 2, 1;
+        Set 2, 2;
+    ])?;
+    assert_eq!(res[8..12], 2u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_conditional_jump_lt_equal_not_taken() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 7;
+        Set 1, 7;
+        JmpLt 0, 1, 5;
+        JmpGtU 0, 1, 5;
+        Set 2, 1;
+    ])?;
+    assert_eq!(res[8..12], 1u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_call_ret() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 1;
+        Call 5;
+        Call 5;
+        Halt;
+        Set 1, 99; // skipped
+        Add 0, 0, 0; // subroutine: double r0
+        Ret;
+    ])?;
+    assert_eq!(res[0..4], 4u32.to_be_bytes());
+    assert_eq!(res[4..8], 0u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_error_stack_underflow() {
+    let mut vm = NomadVm::new(100);
+    let result = vm.execute_program(program![
+        Ret;
+    ]);
+    assert!(matches!(result, Err(VmError::StackUnderflow)));
+}
+
+#[test]
+fn test_error_stack_overflow() {
+    let mut vm = NomadVm::new(10_000);
+    let result = vm.execute_program(program![
+        Call 0;
+    ]);
+    assert!(matches!(result, Err(VmError::StackOverflow)));
+
+    // Reset clears the call stack
+    vm.reset();
+    let result = vm.execute_program(program![
+        Call 2;
+        Halt;
+        Ret;
+    ]);
+    assert!(result.is_ok());
+}
+
 #[test]
 fn test_halt_instruction() -> Result<(), VmError> {
     let mut vm = NomadVm::new(100);
@@ -183,7 +384,7 @@ fn test_wrapping_arithmetic() -> Result<(), VmError> {
 #[test]
 fn test_max_cycles_limit() {
     let mut vm = NomadVm::new(5);
-    let res = vm.execute_program(program![
+    let res = vm.execute_program_report(program![
         Set 0, 1;
         Add 0, 0, 0;
         Add 0, 0, 0;
@@ -194,8 +395,76 @@ fn test_max_cycles_limit() {
     ]);
 
     assert!(res.is_ok());
-    let result = res.unwrap();
-    assert_eq!(result[4..8], 0u32.to_be_bytes());
+    let report = res.unwrap();
+    assert!(!report.halted);
+    assert_eq!(report.cycles, 6);
+    assert_eq!(report.output[4..8], 0u32.to_be_bytes());
+}
+
+#[test]
+fn test_gas_weights() {
+    let registers = program![
+        Set 0, 1;
+        Add 0, 0, 0;
+        Add 0, 0, 0;
+        Add 0, 0, 0;
+        Xor 1, 0, 0;
+        Set 2, 7;
+        Halt;
+    ];
+    let memory = program![
+        Set 0, 1;
+        Store 0, 0;
+        Load 1, 0;
+        Store 1, 4;
+        Load 2, 4;
+        Set 3, 7;
+        Halt;
+    ];
+
+    let report = NomadVm::new(10)
+        .execute_program_report(registers.clone())
+        .unwrap();
+    assert!(report.halted);
+    assert_eq!(report.cycles, 7);
+    assert_eq!(report.gas, 7);
+
+    // Same number of instructions, but memory operations exhaust the budget sooner
+    let report = NomadVm::new(10)
+        .execute_program_report(memory.clone())
+        .unwrap();
+    assert!(!report.halted);
+    assert_eq!(report.cycles, 4);
+    assert_eq!(report.gas, 13);
+    assert_eq!(report.output[8..12], 0u32.to_be_bytes());
+
+    // Uniform weights charge memory operations the same as register operations
+    let report = NomadVm::uniform(10).execute_program_report(memory).unwrap();
+    assert!(report.halted);
+    assert_eq!(report.gas, 7);
+    assert_eq!(report.output[8..12], 1u32.to_be_bytes());
+}
+
+#[test]
+fn test_execution_report_halted() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let report = vm.execute_program_report(program![
+        Set 0, 123;
+        Halt;
+        Set 0, 456;
+    ])?;
+    assert!(report.halted);
+    assert_eq!(report.cycles, 2);
+    assert_eq!(report.output[0..4], 123u32.to_be_bytes());
+
+    vm.reset();
+    let report = vm.execute_program_report(program![
+        Set 0, 1;
+        Set 1, 2;
+    ])?;
+    assert!(report.halted);
+    assert_eq!(report.cycles, 2);
+    Ok(())
 }
 
 #[test]
@@ -206,6 +475,7 @@ fn test_vm_state_reset() -> Result<(), VmError> {
         Set 0, 123;
         Store 0, 500;
     ])?;
+    vm.reset();
 
     let res = vm.execute_program(program![
         Load 0, 500;
@@ -217,6 +487,28 @@ fn test_vm_state_reset() -> Result<(), VmError> {
     Ok(())
 }
 
+#[test]
+fn test_vm_state_persists_without_reset() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+
+    vm.execute_program(program![
+        Set 0, 123;
+        Store 0, 500;
+    ])?;
+
+    // Execution continues from the previous pc with registers and memory intact,
+    // so the first two instructions are skipped
+    let res = vm.execute_program(program![
+        Set 1, 1;
+        Set 1, 2;
+        Load 1, 500;
+    ])?;
+    assert_eq!(res[0..4], 123u32.to_be_bytes());
+    assert_eq!(res[4..8], 123u32.to_be_bytes());
+
+    Ok(())
+}
+
 #[test]
 fn test_error_invalid_register() {
     let bytecode = vec![0x00, 8, 0, 0, 0, 42];
@@ -234,6 +526,16 @@ fn test_error_memory_out_of_bounds_load() {
     assert!(matches!(result, Err(VmError::MemoryOutOfBounds(_))));
 }
 
+#[test]
+fn test_error_memory_out_of_bounds_byte() {
+    let mut vm = NomadVm::new(100);
+    let result = vm.execute_program(program![
+        Load8 0, MEMORY_SIZE as u32 - 1;
+        Store8 0, MEMORY_SIZE as u32;
+    ]);
+    assert!(matches!(result, Err(VmError::MemoryOutOfBounds(addr)) if addr == MEMORY_SIZE));
+}
+
 #[test]
 fn test_error_memory_out_of_bounds_store() {
     let mut vm = NomadVm::new(100);
@@ -274,6 +576,28 @@ fn test_error_pc_out_of_bounds_jmp_ne() {
     assert!(matches!(result, Err(VmError::PcOutOfBounds(999))));
 }
 
+#[test]
+fn test_error_pc_out_of_bounds_jmp_lt() {
+    let mut vm = NomadVm::new(100);
+    let result = vm.execute_program(program![
+        Set 0, 1;
+        Set 1, 2;
+        JmpLt 0, 1, 999;
+    ]);
+    assert!(matches!(result, Err(VmError::PcOutOfBounds(999))));
+}
+
+#[test]
+fn test_error_pc_out_of_bounds_jmp_gt_unsigned() {
+    let mut vm = NomadVm::new(100);
+    let result = vm.execute_program(program![
+        Set 0, 2;
+        Set 1, 1;
+        JmpGtU 0, 1, 999;
+    ]);
+    assert!(matches!(result, Err(VmError::PcOutOfBounds(999))));
+}
+
 #[test]
 fn test_error_invalid_instruction() {
     let bytecode = vec![0x99];
@@ -299,6 +623,140 @@ fn test_error_invalid_program_truncated() {
     assert!(matches!(result, Err(VmError::InvalidProgram)));
 }
 
+#[test]
+fn test_program_encoded_len() {
+    let program = program![
+        Set 0, 1;
+        SetHigh 1, 2;
+        Load8 2, 1000;
+        Add 3, 0, 1;
+        JmpEq 0, 1, 6;
+        Nop;
+        Halt;
+    ];
+    let mut bytecode = Vec::new();
+    program.encode(&mut bytecode).unwrap();
+    assert_eq!(program.encoded_len(), bytecode.len());
+
+    // Programs up to the limit are accepted, and anything larger is rejected
+    let decoded = Program::from_bytes_limited(&bytecode, bytecode.len()).unwrap();
+    assert_eq!(decoded.len(), program.len());
+    let result = Program::from_bytes_limited(&bytecode, bytecode.len() - 1);
+    assert!(matches!(result, Err(VmError::InvalidProgram)));
+}
+
+#[test]
+fn test_opcode_histogram() {
+    let program = program![
+        Set 0, 1;
+        Set 1, 2;
+        Add 2, 0, 1;
+        Xor 3, 2, 0;
+        Set 4, 3;
+        Halt;
+    ];
+    let histogram = program.opcode_histogram();
+    assert_eq!(histogram["SET"], 3);
+    assert_eq!(histogram["ADD"], 1);
+    assert_eq!(histogram.get("SUB"), None);
+    assert_eq!(histogram.values().sum::<usize>(), program.len());
+}
+
+#[test]
+fn test_random_programs_roundtrip() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..1000 {
+        let len = rng.random_range(1..64);
+        let program = testing::random_program(&mut rng, len);
+        program.validate().unwrap();
+
+        let mut bytecode = Vec::new();
+        program.encode(&mut bytecode).unwrap();
+        assert_eq!(Program::from_bytes(&bytecode).unwrap(), program);
+        testing::assert_equivalent(&program, 1000);
+    }
+}
+
+#[test]
+fn test_truncated_instructions_are_rejected() {
+    // One instruction for every opcode
+    let program = program![
+        Set 0, 0x12345678;
+        SetHigh 1, 0x1234;
+        SetLow 1, 0x5678;
+        Load 2, 1000;
+        Store 2, 1000;
+        Load8 3, 1000;
+        Store8 3, 1000;
+        Add 0, 1, 2;
+        Sub 0, 1, 2;
+        Xor 0, 1, 2;
+        Jmp 0;
+        JmpEq 0, 1, 0;
+        JmpNe 0, 1, 0;
+        JmpLt 0, 1, 0;
+        JmpGt 0, 1, 0;
+        JmpLtU 0, 1, 0;
+        JmpGtU 0, 1, 0;
+        Call 0;
+        Ret;
+        Nop;
+        Print 0b1;
+        Halt;
+    ];
+    for instruction in program.iter() {
+        let mut bytecode = Vec::new();
+        instruction.encode(&mut bytecode).unwrap();
+        assert_eq!(bytecode.len(), instruction.size(), "{instruction}");
+        let opcode = Opcode::try_from(bytecode[0]).unwrap();
+        assert_eq!(opcode.size(), instruction.size(), "{instruction}");
+
+        // Decoding consumes exactly the encoded bytes
+        let (decoded, size) = Instruction::from_bytes(&bytecode).unwrap();
+        assert_eq!((&decoded, size), (instruction, bytecode.len()));
+
+        // Any truncated operand is an error, including after a valid instruction
+        for len in 1..bytecode.len() {
+            let result = Instruction::from_bytes(&bytecode[..len]);
+            assert!(
+                matches!(result, Err(VmError::InvalidProgram)),
+                "{instruction}"
+            );
+
+            let mut truncated = vec![Opcode::Nop as u8];
+            truncated.extend_from_slice(&bytecode[..len]);
+            let result = Program::from_bytes(&truncated);
+            assert!(
+                matches!(result, Err(VmError::InvalidProgram)),
+                "{instruction}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_program_encoder() {
+    let program = program![
+        Set 0, 0x12345678;
+        Store 0, 1000;
+        Load8 1, 1001;
+        JmpEq 0, 1, 4;
+        Halt;
+    ];
+    let mut bytecode = Vec::new();
+    program.encode(&mut bytecode).unwrap();
+
+    let mut encoder = Program::encoder();
+    assert!(encoder.is_empty());
+    for instruction in program.iter() {
+        encoder.push(instruction.clone());
+    }
+    assert_eq!(encoder.len(), program.encoded_len());
+    assert_eq!(encoder.finish(), bytecode);
+}
+
 #[test]
 fn test_program_encode_decode_roundtrip() -> Result<(), VmError> {
     let original_program = program![
@@ -307,9 +765,20 @@ fn test_program_encode_decode_roundtrip() -> Result<(), VmError> {
         Add 2, 0, 1;
         Store 2, 1000;
         Load 3, 1000;
-        JmpEq 2, 3, 7;
+        Store8 0, 2000;
+        Load8 5, 2000;
+        SetHigh 6, 0x1234;
+        SetLow 6, 0x5678;
+        JmpEq 2, 3, 12;
         Set 4, 0xFFFFFFFF;
+        Nop;
         Halt;
+        JmpLt 0, 1, 7;
+        JmpGt 0, 1, 7;
+        JmpLtU 0, 1, 7;
+        JmpGtU 0, 1, 7;
+        Call 7;
+        Ret;
     ];
 
     let mut bytecode = Vec::new();
@@ -323,7 +792,9 @@ fn test_program_encode_decode_roundtrip() -> Result<(), VmError> {
     let result1 = vm1.execute_program(original_program)?;
     let result2 = vm2.execute_program(decoded_program)?;
 
-    assert_eq!(result1, result2);
+    if let Some(diff) = VmOutput(result2).diff(result1) {
+        panic!("decoded program output mismatch: {diff}");
+    }
     Ok(())
 }
 
@@ -375,3 +846,237 @@ fn test_print_encode_decode() -> Result<(), VmError> {
 
     Ok(())
 }
+
+#[test]
+fn test_memory_unmapped_reads_zero() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let res = vm.execute_program(program![
+        Set 0, 0xFFFFFFFF;
+        Load 0, 0x10000000;
+    ])?;
+    assert_eq!(res[0..4], 0u32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_across_page_boundary() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let addr = PAGE_SIZE as u32 - 2;
+    let res = vm.execute_program(program![
+        Set 0, 0xCAFEBABE;
+        Store 0, addr;
+        Load 1, addr;
+    ])?;
+    assert_eq!(res[4..8], 0xCAFEBABEu32.to_be_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_memory_pages_allocated_on_write() {
+    let mut memory = Memory::default();
+    assert_eq!(memory.read_u32(0x1000), 0);
+    assert_eq!(memory.allocated_pages(), 0);
+
+    memory.write_u32(PAGE_SIZE - 2, 0x01020304);
+    assert_eq!(memory.allocated_pages(), 2);
+    assert_eq!(memory.read_u32(PAGE_SIZE - 2), 0x01020304);
+
+    memory.clear();
+    assert_eq!(memory.allocated_pages(), 0);
+    assert_eq!(memory.read_u32(PAGE_SIZE - 2), 0);
+}
+
+#[test]
+fn test_step_execution() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let program = program![
+        Set 0, 42;
+        Set 1, 42;
+        JmpEq 0, 1, 4;
+        Set 2, 1;
+        Add 2, 0, 1;
+        Halt;
+    ];
+
+    assert_eq!(vm.step(&program)?, StepResult::Continued);
+    assert_eq!(vm.registers()[0], 42);
+    assert_eq!(vm.pc(), 1);
+
+    assert_eq!(vm.step(&program)?, StepResult::Continued);
+    assert_eq!(vm.step(&program)?, StepResult::Jumped(4));
+    assert_eq!(vm.pc(), 4);
+
+    assert_eq!(vm.step(&program)?, StepResult::Continued);
+    assert_eq!(vm.registers()[2], 84);
+
+    // Halt does not advance the program counter
+    assert_eq!(vm.step(&program)?, StepResult::Halted);
+    assert_eq!(vm.step(&program)?, StepResult::Halted);
+    assert_eq!(vm.pc(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_step_past_end() -> Result<(), VmError> {
+    let mut vm = NomadVm::new(100);
+    let program = program![
+        Set 0, 1;
+    ];
+    assert_eq!(vm.step(&program)?, StepResult::Continued);
+    assert_eq!(vm.step(&program)?, StepResult::Halted);
+    Ok(())
+}
+
+#[test]
+fn test_memory_required() {
+    let program = program![
+        Set 0, 1;
+        Store 0, 1000;
+        Load 1, 200;
+    ];
+    assert_eq!(program.memory_required(), 1004);
+    assert_eq!(program![Set 0, 1;].memory_required(), 0);
+}
+
+#[test]
+fn test_worker_memory_budget() {
+    let mut bytecode = Vec::new();
+    program![
+        Set 0, 0xDEADBEEF;
+        Store 0, 4096;
+    ]
+    .encode(&mut bytecode)
+    .unwrap();
+
+    let mut vm = NomadVm::new(100);
+    let result = vm.handle((bytecode.clone(), Some(1024), Context::current()));
+    assert!(matches!(
+        result,
+        Err(VmError::MemoryBudgetExceeded(4100, 1024))
+    ));
+
+    let result = vm.handle((bytecode.clone(), Some(4100), Context::current()));
+    assert!(result.is_ok());
+    let result = vm.handle((bytecode, None, Context::current()));
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_disassemble() {
+    let program = program![
+        Set 0, 0xDEADBEEF;
+        Load 1, 0x1000;
+        Store 2, 0x2000;
+        Add 0, 1, 2;
+        Sub 3, 4, 5;
+        Xor 6, 7, 0;
+        Jmp 10;
+        JmpEq 3, 4, 2;
+        JmpNe 5, 6, 1;
+        JmpLt 0, 1, 3;
+        JmpGtU 2, 3, 4;
+        Call 5;
+        Ret;
+        Print 0b00000101;
+        Halt;
+    ];
+    let expected = "\
+0000: SET   R0, 0xDEADBEEF
+0001: LOAD  R1, 0x00001000
+0002: STORE R2, 0x00002000
+0003: ADD   R0, R1, R2
+0004: SUB   R3, R4, R5
+0005: XOR   R6, R7, R0
+0006: JMP   0x0000000A
+0007: JMPEQ R3, R4, 0x00000002
+0008: JMPNE R5, R6, 0x00000001
+0009: JMPLT R0, R1, 0x00000003
+0010: JMPGTU R2, R3, 0x00000004
+0011: CALL  0x00000005
+0012: RET   -
+0013: PRINT R0, R2
+0014: HALT  -
+";
+    assert_eq!(program.disassemble(), expected);
+}
+
+#[test]
+fn test_output_diff() {
+    let mut expected = [0u8; 32];
+    expected[12..16].copy_from_slice(&0x11223344u32.to_be_bytes());
+    let mut actual = expected;
+    actual[12..16].copy_from_slice(&0x55667788u32.to_be_bytes());
+
+    let diff = VmOutput(actual).diff(expected).unwrap();
+    assert_eq!(
+        diff.registers,
+        vec![RegisterDiff {
+            register: 3,
+            actual: 0x55667788,
+            expected: 0x11223344,
+        }]
+    );
+    assert_eq!(diff.to_string(), "R3: expected 0x11223344, got 0x55667788");
+    assert_eq!(VmOutput(expected).diff(expected), None);
+}
+
+#[test]
+fn test_validate_program() {
+    let valid = program![
+        Set 0, 1;
+        JmpNe 0, 1, 3;
+        Jmp 0;
+        Halt;
+    ];
+    assert!(valid.validate().is_ok());
+
+    let bad_jump = program![
+        Jmp 5;
+        Halt;
+    ];
+    assert!(matches!(
+        bad_jump.validate(),
+        Err(VmError::PcOutOfBounds(5))
+    ));
+
+    let bad_register = program![
+        Add 0, 9, 1;
+        Halt;
+    ];
+    assert!(matches!(
+        bad_register.validate(),
+        Err(VmError::InvalidRegister(9))
+    ));
+
+    let missing_halt = program![
+        Set 0, 1;
+    ];
+    assert!(matches!(missing_halt.validate(), Err(VmError::MissingHalt)));
+}
+
+#[test]
+fn test_execute_with_validation() {
+    let mut bytecode = Vec::new();
+    program![
+        Set 0, 1;
+        Jmp 5;
+    ]
+    .encode(&mut bytecode)
+    .unwrap();
+
+    // Without validation the first instruction runs before the error surfaces
+    let mut vm = NomadVm::new(100);
+    assert!(matches!(
+        vm.execute(bytecode.clone()),
+        Err(VmError::PcOutOfBounds(5))
+    ));
+    assert_eq!(vm.registers()[0], 1);
+
+    // With validation nothing is executed
+    let mut vm = NomadVm::new(100).with_validation(true);
+    assert!(matches!(
+        vm.execute(bytecode),
+        Err(VmError::PcOutOfBounds(5))
+    ));
+    assert_eq!(vm.registers()[0], 0);
+}